@@ -0,0 +1,358 @@
+mod sparse;
+
+pub use sparse::{CountRule, SparseAutomaton, SparseNeighborhood};
+
+/// Visits the indices of all neighbors of a cell, given the cells of the current generation
+pub trait Neighborhood<C> {
+    fn neighbors(&self, cells: &[C], index: usize, visit: &mut dyn FnMut(usize));
+}
+
+impl<C, F> Neighborhood<C> for F
+where
+    F: Fn(&[C], usize, &mut dyn FnMut(usize)),
+{
+    fn neighbors(&self, cells: &[C], index: usize, visit: &mut dyn FnMut(usize)) {
+        self(cells, index, visit)
+    }
+}
+
+/// Returns the next state of a cell, given its current state and the states of its neighbors
+pub trait Rule<C> {
+    fn next(&self, cell: &C, neighbors: &[&C]) -> C;
+
+    /// Static cells never change, they are neither updated nor get any neighbors
+    fn is_static(&self, _cell: &C) -> bool {
+        false
+    }
+}
+
+impl<C, F> Rule<C> for F
+where
+    F: Fn(&C, &[&C]) -> C,
+{
+    fn next(&self, cell: &C, neighbors: &[&C]) -> C {
+        self(cell, neighbors)
+    }
+}
+
+/// The long term behavior of an automaton
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The cells do not change anymore from the given generation on
+    Stabilized { generation: usize },
+    /// From generation `start` on the cells repeat every `period` generations
+    Oscillating { start: usize, period: usize },
+    /// Neither happened within the maximum number of generations
+    LimitReached,
+}
+
+/// A compact adjacency list, the neighbors of all cells are stored in a single vector
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Adjacency {
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+}
+
+impl Adjacency {
+    /// Collects the neighbors of `len` cells, `visit_neighbors` is called once per cell
+    pub fn build<F>(len: usize, mut visit_neighbors: F) -> Self
+    where
+        F: FnMut(usize, &mut dyn FnMut(usize)),
+    {
+        let mut offsets = Vec::with_capacity(len + 1);
+        let mut targets = Vec::new();
+
+        offsets.push(0);
+        for index in 0..len {
+            visit_neighbors(index, &mut |neighbor| targets.push(neighbor));
+            offsets.push(targets.len());
+        }
+
+        Self { offsets, targets }
+    }
+
+    /// Returns the neighbors of the cell
+    pub fn get(&self, index: usize) -> &[usize] {
+        &self.targets[self.offsets[index]..self.offsets[index + 1]]
+    }
+
+    /// Returns the reversed adjacency, all cells that have the cell as neighbor
+    pub fn transpose(&self) -> Self {
+        let len = self.offsets.len() - 1;
+
+        let mut offsets = vec![0; len + 1];
+        for &target in &self.targets {
+            offsets[target + 1] += 1;
+        }
+        for index in 0..len {
+            offsets[index + 1] += offsets[index];
+        }
+
+        let mut fill = offsets.clone();
+        let mut targets = vec![0; self.targets.len()];
+        for index in 0..len {
+            for &target in self.get(index) {
+                targets[fill[target]] = index;
+                fill[target] += 1;
+            }
+        }
+
+        Self { offsets, targets }
+    }
+}
+
+/// A cellular automaton over a fixed number of cells.
+///
+/// The geometry is defined by the neighborhood, the behavior by the transition rule,
+/// all cells are updated at once per generation. The neighborhood is evaluated once on
+/// construction, therefore it must not depend on states of cells that change.
+/// Each generation only evaluates the cells that changed or have a changed neighbor.
+#[derive(Clone, Debug)]
+pub struct Automaton<C, R> {
+    cells: Vec<C>,
+    rule: R,
+    neighbors: Adjacency,
+    dependents: Adjacency,
+    active: Vec<usize>,
+    scheduled: Vec<bool>,
+}
+
+impl<C, R> Automaton<C, R>
+where
+    C: Clone + PartialEq,
+    R: Rule<C>,
+{
+    pub fn new<N: Neighborhood<C>>(cells: Vec<C>, neighborhood: N, rule: R) -> Self {
+        let neighbors = Adjacency::build(cells.len(), |index, visit| {
+            if !rule.is_static(&cells[index]) {
+                neighborhood.neighbors(&cells, index, visit);
+            }
+        });
+        let dependents = neighbors.transpose();
+        let active = (0..cells.len())
+            .filter(|&index| !rule.is_static(&cells[index]))
+            .collect();
+        let scheduled = vec![false; cells.len()];
+
+        Self { cells, rule, neighbors, dependents, active, scheduled }
+    }
+
+    pub fn cells(&self) -> &[C] {
+        &self.cells
+    }
+
+    pub fn into_cells(self) -> Vec<C> {
+        self.cells
+    }
+
+    /// Calculates the next generation, returns true if any cell changed
+    pub fn step(&mut self) -> bool {
+        let mut changes = Vec::new();
+        let mut neighbors = Vec::new();
+
+        for &index in &self.active {
+            neighbors.clear();
+            neighbors.extend(self.neighbors.get(index).iter().map(|&neighbor| &self.cells[neighbor]));
+
+            let next = self.rule.next(&self.cells[index], &neighbors);
+            if next != self.cells[index] {
+                changes.push((index, next));
+            }
+        }
+
+        self.active.clear();
+        for &(index, _) in &changes {
+            for &cell in std::iter::once(&index).chain(self.dependents.get(index)) {
+                if !self.scheduled[cell] {
+                    self.scheduled[cell] = true;
+                    self.active.push(cell);
+                }
+            }
+        }
+        for &index in &self.active {
+            self.scheduled[index] = false;
+        }
+
+        let changed = !changes.is_empty();
+        for (index, cell) in changes {
+            self.cells[index] = cell;
+        }
+        changed
+    }
+
+    /// Runs at most `limit` generations until the cells stabilize or repeat.
+    ///
+    /// Repetitions are found with Brent's cycle detection, only the cells of a single checkpoint
+    /// generation are kept. A second run from the initial cells then finds the start of the cycle,
+    /// the automaton ends in generation `start + period`.
+    pub fn run(&mut self, limit: usize) -> Outcome
+    where
+        R: Clone,
+    {
+        let initial = self.clone();
+        let mut checkpoint = self.cells.clone();
+        let mut power = 1;
+        let mut period = 0;
+
+        for generation in 0..limit {
+            if !self.step() {
+                return Outcome::Stabilized { generation };
+            }
+            period += 1;
+
+            if self.cells == checkpoint {
+                let mut tortoise = initial.clone();
+                let mut hare = initial;
+                for _ in 0..period {
+                    hare.step();
+                }
+
+                let mut start = 0;
+                while tortoise.cells != hare.cells {
+                    tortoise.step();
+                    hare.step();
+                    start += 1;
+                }
+
+                *self = hare;
+                return Outcome::Oscillating { start, period };
+            }
+
+            if period == power {
+                checkpoint.clone_from(&self.cells);
+                power *= 2;
+                period = 0;
+            }
+        }
+
+        Outcome::LimitReached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Adjacency, Automaton, Outcome, Rule};
+
+    /// A cell whose hash ignores its value, every generation would collide in a hash map
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct Colliding(u32);
+
+    impl std::hash::Hash for Colliding {
+        fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {}
+    }
+
+    /// Takes the maximum of the cell and its neighbors, `None` cells never change
+    struct Maximum;
+
+    impl Rule<Option<u32>> for Maximum {
+        fn next(&self, cell: &Option<u32>, neighbors: &[&Option<u32>]) -> Option<u32> {
+            cell.map(|value| value.max(neighbors.iter().filter_map(|n| **n).max().unwrap_or(0)))
+        }
+
+        fn is_static(&self, cell: &Option<u32>) -> bool {
+            cell.is_none()
+        }
+    }
+
+    /// Conway's Game of Life on a small torus
+    #[test]
+    fn test_game_of_life_blinker() {
+        const SIZE: usize = 5;
+
+        let torus = |_: &[bool], index: usize, visit: &mut dyn FnMut(usize)| {
+            let (x, y) = (index % SIZE, index / SIZE);
+            for (dx, dy) in [(SIZE - 1, SIZE - 1), (0, SIZE - 1), (1, SIZE - 1), (SIZE - 1, 0), (1, 0), (SIZE - 1, 1), (0, 1), (1, 1)].iter() {
+                visit((x + dx) % SIZE + (y + dy) % SIZE * SIZE);
+            }
+        };
+        let life = |alive: &bool, neighbors: &[&bool]| {
+            let count = neighbors.iter().filter(|&&&n| n).count();
+            count == 3 || (*alive && count == 2)
+        };
+
+        let mut cells = vec![false; SIZE * SIZE];
+        cells[11] = true;
+        cells[12] = true;
+        cells[13] = true;
+
+        let mut automaton = Automaton::new(cells.clone(), torus, life);
+        assert!(automaton.step());
+        assert_eq!(vec![7, 12, 17], automaton.clone().into_cells().iter().enumerate().filter(|(_, &a)| a).map(|(i, _)| i).collect::<Vec<_>>());
+        assert!(automaton.step());
+        assert_eq!(cells, automaton.clone().into_cells());
+
+        assert_eq!(Outcome::Oscillating { start: 0, period: 2 }, automaton.run(100));
+    }
+
+    #[test]
+    fn test_adjacency_transpose() {
+        let adjacency = Adjacency::build(4, |index, visit| {
+            if index < 3 {
+                visit(index + 1);
+                visit(3);
+            }
+        });
+
+        assert_eq!(&[1, 3], adjacency.get(0));
+        assert_eq!(&[3, 3], adjacency.get(2));
+        assert!(adjacency.get(3).is_empty());
+
+        let reversed = adjacency.transpose();
+        assert!(reversed.get(0).is_empty());
+        assert_eq!(&[0], reversed.get(1));
+        assert_eq!(&[0, 1, 2, 2], reversed.get(3));
+    }
+
+    #[test]
+    fn test_static_cells_are_skipped() {
+        // a line of cells, each one sees its direct neighbors
+        let line = |cells: &[Option<u32>], index: usize, visit: &mut dyn FnMut(usize)| {
+            if index > 0 { visit(index - 1) }
+            if index + 1 < cells.len() { visit(index + 1) }
+        };
+        let mut automaton = Automaton::new(vec![Some(1), None, Some(2), Some(0), Some(5)], line, Maximum);
+
+        assert!(automaton.neighbors.get(1).is_empty());
+        assert_eq!(vec![0, 2, 3, 4], automaton.active);
+
+        assert!(automaton.step());
+        assert_eq!(vec![3, 2, 4], automaton.active);
+        assert!(automaton.step());
+        assert_eq!(vec![2, 3], automaton.active);
+        assert!(!automaton.step());
+        assert!(automaton.active.is_empty());
+        assert_eq!(vec![Some(1), None, Some(5), Some(5), Some(5)], automaton.into_cells());
+    }
+
+    #[test]
+    fn test_run_outcomes() {
+        let line = |cells: &[u32], index: usize, visit: &mut dyn FnMut(usize)| {
+            if index + 1 < cells.len() { visit(index + 1) }
+        };
+
+        // every cell takes over the value of its right neighbor
+        let shift = |cell: &u32, neighbors: &[&u32]| neighbors.first().map_or(*cell, |&&n| n);
+        let mut automaton = Automaton::new(vec![1, 2, 3, 4], line, shift);
+        assert_eq!(Outcome::Stabilized { generation: 3 }, automaton.run(100));
+        assert_eq!(vec![4, 4, 4, 4], automaton.into_cells());
+
+        // every cell counts up modulo 5, after a warm up of two generations
+        let count = |cell: &u32, _: &[&u32]| if *cell < 2 { *cell + 3 } else { (*cell - 2 + 1) % 5 + 2 };
+        assert_eq!(Outcome::Oscillating { start: 1, period: 5 }, Automaton::new(vec![0], line, count).run(100));
+        assert_eq!(Outcome::LimitReached, Automaton::new(vec![0], line, count).run(4));
+
+        // a long warm up before a short cycle, the automaton ends where the cycle repeats
+        let warm_up = |cell: &u32, _: &[&u32]| if *cell < 20 { *cell + 1 } else { (*cell - 20 + 1) % 3 + 20 };
+        let mut automaton = Automaton::new(vec![0], line, warm_up);
+        assert_eq!(Outcome::Oscillating { start: 20, period: 3 }, automaton.run(100));
+        assert_eq!(vec![20], automaton.into_cells());
+    }
+
+    #[test]
+    fn test_hash_collisions_are_no_repetition() {
+        let none = |_: &[Colliding], _: usize, _: &mut dyn FnMut(usize)| {};
+        let count = |cell: &Colliding, _: &[&Colliding]| Colliding((cell.0 + 1) % 3);
+
+        assert_eq!(Outcome::Oscillating { start: 0, period: 3 }, Automaton::new(vec![Colliding(0)], none, count).run(100));
+    }
+}
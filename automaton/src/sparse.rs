@@ -0,0 +1,142 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+/// Visits all cells that count the given active cell as neighbor, the grid may grow in every direction
+pub trait SparseNeighborhood<P> {
+    fn neighbors(&self, cell: &P, visit: &mut dyn FnMut(P));
+}
+
+impl<P, F> SparseNeighborhood<P> for F
+where
+    F: Fn(&P, &mut dyn FnMut(P)),
+{
+    fn neighbors(&self, cell: &P, visit: &mut dyn FnMut(P)) {
+        self(cell, visit)
+    }
+}
+
+/// Returns true if a cell is active in the next generation, given its state and number of active neighbors
+pub trait CountRule {
+    fn next(&self, active: bool, count: usize) -> bool;
+}
+
+impl<F> CountRule for F
+where
+    F: Fn(bool, usize) -> bool,
+{
+    fn next(&self, active: bool, count: usize) -> bool {
+        self(active, count)
+    }
+}
+
+/// A cellular automaton of active and inactive cells on an unbounded grid.
+///
+/// Only the active cells are stored. Each generation every active cell adds one to the count
+/// of all its neighbors, the rule then decides from the counts which cells stay or become active.
+#[derive(Clone, Debug)]
+pub struct SparseAutomaton<P, N, R> {
+    cells: HashSet<P>,
+    neighborhood: N,
+    rule: R,
+}
+
+impl<P, N, R> SparseAutomaton<P, N, R>
+where
+    P: Clone + Eq + Hash,
+    N: SparseNeighborhood<P>,
+    R: CountRule,
+{
+    pub fn new(cells: HashSet<P>, neighborhood: N, rule: R) -> Self {
+        Self { cells, neighborhood, rule }
+    }
+
+    /// Returns the active cells
+    pub fn cells(&self) -> &HashSet<P> {
+        &self.cells
+    }
+
+    pub fn into_cells(self) -> HashSet<P> {
+        self.cells
+    }
+
+    /// Calculates the next generation, returns true if any cell changed
+    pub fn step(&mut self) -> bool {
+        let mut counts = HashMap::new();
+        for cell in &self.cells {
+            self.neighborhood.neighbors(cell, &mut |neighbor| *counts.entry(neighbor).or_insert(0) += 1);
+        }
+
+        // active cells without any active neighbor do not show up in the counts
+        let mut next = self
+            .cells
+            .iter()
+            .filter(|&cell| !counts.contains_key(cell) && self.rule.next(true, 0))
+            .cloned()
+            .collect::<HashSet<_>>();
+        next.extend(
+            counts
+                .into_iter()
+                .filter(|(cell, count)| self.rule.next(self.cells.contains(cell), *count))
+                .map(|(cell, _)| cell),
+        );
+
+        let changed = next != self.cells;
+        self.cells = next;
+        changed
+    }
+
+    /// Calculates the given number of generations
+    pub fn run(&mut self, generations: usize) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::SparseAutomaton;
+
+    fn plane(cell: &(i32, i32), visit: &mut dyn FnMut((i32, i32))) {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) != (0, 0) {
+                    visit((cell.0 + dx, cell.1 + dy));
+                }
+            }
+        }
+    }
+
+    fn life(active: bool, count: usize) -> bool {
+        count == 3 || (active && count == 2)
+    }
+
+    #[test]
+    fn test_game_of_life_glider() {
+        let glider = vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].into_iter().collect::<HashSet<_>>();
+        let mut automaton = SparseAutomaton::new(glider.clone(), plane, life);
+
+        // a glider moves one cell diagonally every four generations
+        automaton.run(4);
+        let moved = glider.iter().map(|&(x, y)| (x + 1, y + 1)).collect::<HashSet<_>>();
+        assert_eq!(&moved, automaton.cells());
+    }
+
+    #[test]
+    fn test_lonely_cells() {
+        // a lonely cell dies with the life rule, but stays with a rule that keeps every active cell
+        let mut automaton = SparseAutomaton::new(vec![(0, 0)].into_iter().collect(), plane, life);
+        assert!(automaton.step());
+        assert!(automaton.cells().is_empty());
+        assert!(!automaton.step());
+
+        let keep = |active: bool, count: usize| active || count == 8;
+        let mut automaton = SparseAutomaton::new(vec![(0, 0)].into_iter().collect(), plane, keep);
+        assert!(!automaton.step());
+        assert_eq!(vec![(0, 0)], automaton.into_cells().into_iter().collect::<Vec<_>>());
+    }
+}
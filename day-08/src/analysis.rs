@@ -0,0 +1,170 @@
+use std::fmt::{self, Display};
+
+use crate::Instruction;
+
+/// The target of an edge in the control flow graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// Another instruction of the program
+    Instruction(usize),
+    /// The position right after the last instruction, the program terminates
+    End,
+    /// A position outside of the program
+    OutOfBounds(i64),
+}
+
+/// The outcome of the program, derived from the control flow graph only
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Terminates,
+    /// The program enters the loop of the given instructions and never leaves it
+    InfiniteLoop(Vec<usize>),
+    /// The program jumps outside of its bounds from the given instruction
+    OutOfBounds { from: usize, target: i64 },
+}
+
+/// The result of the static analysis of a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// The control flow graph, the single successor of every instruction
+    pub edges: Vec<Target>,
+    /// All instructions that can never be reached from the first instruction
+    pub unreachable: Vec<usize>,
+    /// All cycles of the control flow graph, each one loops forever once it is entered
+    pub loops: Vec<Vec<usize>>,
+    pub outcome: Outcome,
+}
+
+/// Builds the control flow graph of the program.
+///
+/// The accumulator never influences a jump, therefore every instruction has exactly one successor.
+pub fn control_flow_graph(instructions: &[Instruction]) -> Vec<Target> {
+    instructions
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| {
+            let target = match instruction {
+                Instruction::Jmp(offset) => index as i64 + offset,
+                _ => index as i64 + 1,
+            };
+
+            if target == instructions.len() as i64 {
+                Target::End
+            } else if target < 0 || target > instructions.len() as i64 {
+                Target::OutOfBounds(target)
+            } else {
+                Target::Instruction(target as usize)
+            }
+        })
+        .collect()
+}
+
+/// Analyzes the program without executing any instruction
+pub fn analyze(instructions: &[Instruction]) -> Analysis {
+    let edges = control_flow_graph(instructions);
+
+    // walk every path once, a path that runs into itself closes a cycle
+    let mut loops = Vec::new();
+    let mut walked = vec![false; edges.len()];
+    for start in 0..edges.len() {
+        let mut path = Vec::new();
+        let mut node = start;
+
+        while !walked[node] {
+            walked[node] = true;
+            path.push(node);
+
+            match edges[node] {
+                Target::Instruction(next) => node = next,
+                _ => break,
+            }
+        }
+
+        if let Some(position) = path.iter().position(|&index| index == node) {
+            if matches!(edges[*path.last().unwrap()], Target::Instruction(_)) {
+                loops.push(path[position..].to_vec());
+            }
+        }
+    }
+
+    // follow the single path from the first instruction
+    let mut reachable = vec![false; edges.len()];
+    let mut node = 0;
+    let outcome = loop {
+        if node >= edges.len() {
+            break Outcome::Terminates;
+        }
+        if reachable[node] {
+            let cycle = loops.iter().find(|cycle| cycle.contains(&node)).cloned().unwrap_or_default();
+            break Outcome::InfiniteLoop(cycle);
+        }
+        reachable[node] = true;
+
+        match edges[node] {
+            Target::Instruction(next) => node = next,
+            Target::End => break Outcome::Terminates,
+            Target::OutOfBounds(target) => break Outcome::OutOfBounds { from: node, target },
+        }
+    };
+
+    let unreachable = (0..edges.len()).filter(|&index| !reachable[index]).collect();
+
+    Analysis { edges, unreachable, loops, outcome }
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Instructions: {}", self.edges.len())?;
+        writeln!(f, "Unreachable: {} {:?}", self.unreachable.len(), self.unreachable)?;
+        writeln!(f, "Loops: {}", self.loops.len())?;
+        for cycle in &self.loops {
+            writeln!(f, "  {:?}", cycle)?;
+        }
+        write!(f, "Outcome: {:?}", self.outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outcome, Target, analyze, control_flow_graph};
+    use crate::Instruction::*;
+
+    #[test]
+    fn test_control_flow_graph() {
+        let input = vec![Nop(0), Jmp(2), Acc(1), Jmp(-4), Jmp(1)];
+        let expected = vec![
+            Target::Instruction(1),
+            Target::Instruction(3),
+            Target::Instruction(3),
+            Target::OutOfBounds(-1),
+            Target::End,
+        ];
+        assert_eq!(expected, control_flow_graph(&input));
+    }
+
+    #[test]
+    fn test_analyze_infinite_loop() {
+        let input = vec![Nop(0), Acc(1), Jmp(4), Acc(3), Jmp(-3), Acc(-99), Acc(1), Jmp(-4), Acc(6)];
+        let analysis = analyze(&input);
+
+        assert_eq!(vec![5, 8], analysis.unreachable);
+        assert_eq!(vec![vec![1, 2, 6, 7, 3, 4]], analysis.loops);
+        assert_eq!(Outcome::InfiniteLoop(vec![1, 2, 6, 7, 3, 4]), analysis.outcome);
+    }
+
+    #[test]
+    fn test_analyze_terminating_program_with_dead_loop() {
+        let input = vec![Jmp(3), Acc(1), Jmp(-1), Acc(2)];
+        let analysis = analyze(&input);
+
+        assert_eq!(vec![1, 2], analysis.unreachable);
+        assert_eq!(vec![vec![1, 2]], analysis.loops);
+        assert_eq!(Outcome::Terminates, analysis.outcome);
+    }
+
+    #[test]
+    fn test_analyze_out_of_bounds() {
+        let input = vec![Acc(1), Jmp(-5)];
+        assert_eq!(Outcome::OutOfBounds { from: 1, target: -4 }, analyze(&input).outcome);
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::anyhow;
+use std::collections::HashMap;
+
+use crate::Instruction;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    Value(i64),
+    Label(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Opcode {
+    Acc,
+    Jmp,
+    Nop,
+}
+
+/// A single line of assembly, a label, an instruction, both or none
+#[derive(Debug, PartialEq, Eq)]
+struct Statement {
+    label: Option<String>,
+    instruction: Option<(Opcode, Operand)>,
+}
+
+peg::parser!{
+    grammar asm_parser() for str {
+        rule _() = [' ' | '\t']*
+
+        rule ident() -> &'input str
+            = $(['a'..='z' | 'A'..='Z' | '_']['a'..='z' | 'A'..='Z' | '0'..='9' | '_']*)
+
+        rule number() -> i64
+            = s:$(['+' | '-']?['0'..='9']+) {? s.parse().or(Err("number")) }
+
+        rule operand() -> Operand
+            = n:number() { Operand::Value(n) }
+            / l:ident() { Operand::Label(l.to_string()) }
+
+        rule opcode() -> Opcode
+            = "acc" { Opcode::Acc }
+            / "jmp" { Opcode::Jmp }
+            / "nop" { Opcode::Nop }
+
+        rule instruction() -> (Opcode, Operand)
+            = o:opcode() [' ' | '\t']+ a:operand() { (o, a) }
+
+        rule label() -> String
+            = l:ident() _ ":" { l.to_string() }
+
+        rule comment()
+            = ['#' | ';'] [_]*
+
+        pub(crate) rule line() -> Statement
+            = _ label:label()? _ instruction:instruction()? _ comment()? { Statement { label, instruction } }
+    }
+}
+
+/// Compiles assembly text with labels and comments into boot code instructions.
+///
+/// Every line holds an optional label (`loop:`), an optional instruction and an optional
+/// comment starting with `#` or `;`. The operand of `jmp` and `nop` can be a label,
+/// it is replaced with the relative offset to the labelled instruction.
+pub fn assemble(source: &str) -> anyhow::Result<Vec<Instruction>> {
+    let mut labels = HashMap::new();
+    let mut instructions = Vec::new();
+
+    for (number, line) in source.lines().enumerate() {
+        let statement = asm_parser::line(line).map_err(|e| anyhow!("Line {}: {}", number + 1, e))?;
+
+        if let Some(label) = statement.label {
+            if labels.insert(label.clone(), instructions.len()).is_some() {
+                return Err(anyhow!("Line {}: duplicate label '{}'", number + 1, label));
+            }
+        }
+        if let Some(instruction) = statement.instruction {
+            instructions.push((number + 1, instruction));
+        }
+    }
+
+    instructions
+        .into_iter()
+        .enumerate()
+        .map(|(index, (number, (opcode, operand)))| {
+            let value = match operand {
+                Operand::Value(value) => value,
+                Operand::Label(label) if opcode != Opcode::Acc => match labels.get(&label) {
+                    Some(target) => *target as i64 - index as i64,
+                    None => return Err(anyhow!("Line {}: unknown label '{}'", number, label)),
+                },
+                Operand::Label(label) => return Err(anyhow!("Line {}: acc does not accept label '{}'", number, label)),
+            };
+
+            Ok(match opcode {
+                Opcode::Acc => Instruction::Acc(value),
+                Opcode::Jmp => Instruction::Jmp(value),
+                Opcode::Nop => Instruction::Nop(value),
+            })
+        })
+        .collect()
+}
+
+/// Prints the instructions back as canonical boot code, one instruction per line
+pub fn disassemble(instructions: &[Instruction]) -> String {
+    instructions
+        .iter()
+        .map(|instruction| format!("{}\n", instruction))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, disassemble};
+    use crate::{Instruction::*, parse_line};
+
+    #[test]
+    fn test_assemble_labels_and_comments() {
+        let source = r#"
+            # count down the accumulator
+            start:  acc +3
+            loop:   acc -1      ; decrement
+                    jmp end
+            end:
+                    nop start
+                    jmp loop
+        "#;
+
+        let expected = vec![Acc(3), Acc(-1), Jmp(1), Nop(-3), Jmp(-3)];
+        assert_eq!(expected, assemble(source).unwrap());
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        assert!(assemble("jmp missing").is_err());
+        assert!(assemble("a: nop +0\na: nop +0").is_err());
+        assert!(assemble("a: acc a").is_err());
+        assert!(assemble("mul +2").is_err());
+    }
+
+    #[test]
+    fn test_disassemble_round_trip() {
+        let instructions = vec![Nop(0), Acc(1), Jmp(4), Acc(-99), Jmp(-3)];
+        let text = disassemble(&instructions);
+
+        assert_eq!("nop +0\nacc +1\njmp +4\nacc -99\njmp -3\n", text);
+        assert_eq!(instructions, text.lines().map(|line| parse_line(line).unwrap()).collect::<Vec<_>>());
+        assert_eq!(instructions, assemble(&text).unwrap());
+    }
+}
@@ -0,0 +1,293 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    io::{self, BufRead, Write},
+};
+
+use crate::machine::{ExitState, Machine, Operation};
+
+/// A single executed instruction with the accumulator before and after
+#[derive(Debug, PartialEq, Eq)]
+pub struct TraceEntry<'a, I> {
+    pub pc: i64,
+    pub instruction: &'a I,
+    pub acc_before: i64,
+    pub acc_after: i64,
+}
+
+impl<'a, I: Operation> Display for TraceEntry<'a, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>4}: {:<12} acc {} -> {}", self.pc, format!("{:?}", self.instruction), self.acc_before, self.acc_after)
+    }
+}
+
+/// The reason the debugger stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The program counter reached a breakpoint, the instruction is not executed yet
+    Breakpoint(i64),
+    /// The watched accumulator changed by the instruction at `pc`
+    Watch { pc: i64, old: i64, new: i64 },
+    /// The machine stopped
+    Exit(ExitState),
+}
+
+/// Runs a program step by step, records a trace of all executed instructions
+pub struct Debugger<'a, I: Operation> {
+    machine: Machine<'a, I>,
+    breakpoints: HashSet<i64>,
+    watch_acc: bool,
+    trace: Vec<TraceEntry<'a, I>>,
+    exit: Option<ExitState>,
+    /// The pc of the last reported breakpoint, as long as it was not executed yet
+    reported: Option<i64>,
+}
+
+impl<'a, I: Operation> Debugger<'a, I> {
+    pub fn new(program: &'a [I]) -> Self {
+        Self {
+            machine: Machine::new(program),
+            breakpoints: HashSet::new(),
+            watch_acc: false,
+            trace: Vec::new(),
+            exit: None,
+            reported: None,
+        }
+    }
+
+    pub fn machine(&self) -> &Machine<'a, I> {
+        &self.machine
+    }
+
+    /// Adds a breakpoint, returns false if it was already set
+    pub fn add_breakpoint(&mut self, pc: i64) -> bool {
+        self.breakpoints.insert(pc)
+    }
+
+    /// Removes a breakpoint, returns false if there was none
+    pub fn remove_breakpoint(&mut self, pc: i64) -> bool {
+        self.breakpoints.remove(&pc)
+    }
+
+    /// Stops the debugger every time the accumulator changes
+    pub fn watch_acc(&mut self, watch: bool) {
+        self.watch_acc = watch;
+    }
+
+    pub fn trace(&self) -> &[TraceEntry<'a, I>] {
+        &self.trace
+    }
+
+    /// Executes a single instruction
+    pub fn step(&mut self) -> Option<Event> {
+        if let Some(exit) = &self.exit {
+            return Some(Event::Exit(exit.clone()));
+        }
+
+        let pc = self.machine.pc();
+        let instruction = self.machine.current();
+        let acc_before = self.machine.acc();
+        let steps = self.machine.steps();
+
+        let exit = self.machine.step();
+        self.reported = None;
+        let acc_after = self.machine.acc();
+
+        if let (Some(instruction), true) = (instruction, self.machine.steps() > steps) {
+            self.trace.push(TraceEntry { pc, instruction, acc_before, acc_after });
+        }
+
+        if let Some(exit) = exit {
+            self.exit = Some(exit.clone());
+            return Some(Event::Exit(exit));
+        }
+        if self.watch_acc && acc_before != acc_after {
+            return Some(Event::Watch { pc, old: acc_before, new: acc_after });
+        }
+
+        None
+    }
+
+    /// Runs until a breakpoint is hit, the watched accumulator changes or the machine stops.
+    ///
+    /// A breakpoint at the current instruction is only skipped if it was just reported,
+    /// to continue after it was hit.
+    pub fn resume(&mut self) -> Event {
+        loop {
+            let pc = self.machine.pc();
+            if self.exit.is_none() && self.breakpoints.contains(&pc) && self.reported != Some(pc) {
+                self.reported = Some(pc);
+                return Event::Breakpoint(pc);
+            }
+            if let Some(event) = self.step() {
+                return event;
+            }
+        }
+    }
+
+    /// Returns the executed instructions of the detected infinite loop, in order.
+    ///
+    /// The cycle starts with the instruction that would have been executed a second time.
+    pub fn loop_cycle(&self) -> Option<&[TraceEntry<'a, I>]> {
+        match self.exit {
+            Some(ExitState::InfiniteLoop(_)) => {
+                let pc = self.machine.pc();
+                let start = self.trace.iter().rposition(|entry| entry.pc == pc)?;
+                Some(&self.trace[start..])
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Prints the trace of the program with the detected loop cycle
+pub fn print_trace<I: Operation>(program: &[I]) {
+    let mut debugger = Debugger::new(program);
+    let exit = loop {
+        if let Event::Exit(exit) = debugger.resume() {
+            break exit;
+        }
+    };
+
+    for entry in debugger.trace() {
+        println!("{}", entry);
+    }
+    println!("{:?}", exit);
+
+    if let Some(cycle) = debugger.loop_cycle() {
+        let pcs = cycle.iter().map(|entry| entry.pc.to_string()).collect::<Vec<_>>();
+        println!("Loop cycle of {} instructions: {}", cycle.len(), pcs.join(" -> "));
+    }
+}
+
+/// Runs an interactive debugger session reading commands from stdin
+pub fn interactive<I: Operation>(program: &[I]) -> anyhow::Result<()> {
+    let mut debugger = Debugger::new(program);
+    let mut watch = false;
+    let stdin = io::stdin();
+
+    println!("Commands: s(tep), c(ontinue), b(reak) <pc>, d(elete) <pc>, w(atch), t(race), l(oop), p(rint), q(uit)");
+    print!("> ");
+    io::stdout().flush()?;
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let mut args = line.split_whitespace();
+
+        match (args.next(), args.next().map(str::parse::<i64>)) {
+            (Some("s"), _) => match debugger.step() {
+                Some(event) => println!("{:?}", event),
+                None => println!("{}", debugger.trace().last().map_or(String::new(), ToString::to_string)),
+            },
+            (Some("c"), _) => println!("{:?}", debugger.resume()),
+            (Some("b"), Some(Ok(pc))) => { debugger.add_breakpoint(pc); }
+            (Some("d"), Some(Ok(pc))) => { debugger.remove_breakpoint(pc); }
+            (Some("w"), _) => {
+                watch = !watch;
+                debugger.watch_acc(watch);
+                println!("Watch accumulator: {}", watch);
+            }
+            (Some("t"), _) => debugger.trace().iter().for_each(|entry| println!("{}", entry)),
+            (Some("l"), _) => match debugger.loop_cycle() {
+                Some(cycle) => cycle.iter().for_each(|entry| println!("{}", entry)),
+                None => println!("No loop detected"),
+            },
+            (Some("p"), _) => {
+                let machine = debugger.machine();
+                println!("pc: {}, registers: {:?}, next: {:?}", machine.pc(), machine.registers(), machine.current());
+            }
+            (Some("q"), _) => break,
+            _ => println!("Unknown command"),
+        }
+
+        print!("> ");
+        io::stdout().flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Debugger, Event};
+    use crate::{Instruction, machine::ExitState};
+
+    fn program() -> Vec<Instruction> {
+        vec![
+            Instruction::Nop(0),
+            Instruction::Acc(1),
+            Instruction::Jmp(4),
+            Instruction::Acc(3),
+            Instruction::Jmp(-3),
+            Instruction::Acc(-99),
+            Instruction::Acc(1),
+            Instruction::Jmp(-4),
+            Instruction::Acc(6),
+        ]
+    }
+
+    #[test]
+    fn test_breakpoints_and_single_step() {
+        let program = program();
+        let mut debugger = Debugger::new(&program);
+        debugger.add_breakpoint(6);
+
+        assert_eq!(Event::Breakpoint(6), debugger.resume());
+        assert_eq!(1, debugger.machine().acc());
+        assert_eq!(None, debugger.step());
+        assert_eq!(2, debugger.machine().acc());
+        assert_eq!(7, debugger.machine().pc());
+    }
+
+    #[test]
+    fn test_breakpoint_at_start() {
+        let program = program();
+        let mut debugger = Debugger::new(&program);
+        debugger.add_breakpoint(0);
+
+        assert_eq!(Event::Breakpoint(0), debugger.resume());
+        assert_eq!(0, debugger.machine().steps());
+        assert_eq!(Event::Exit(ExitState::InfiniteLoop(5)), debugger.resume());
+    }
+
+    #[test]
+    fn test_breakpoint_after_watch() {
+        let program = program();
+        let mut debugger = Debugger::new(&program);
+        debugger.watch_acc(true);
+        debugger.add_breakpoint(2);
+
+        assert_eq!(Event::Watch { pc: 1, old: 0, new: 1 }, debugger.resume());
+        assert_eq!(Event::Breakpoint(2), debugger.resume());
+        assert_eq!(Event::Watch { pc: 6, old: 1, new: 2 }, debugger.resume());
+    }
+
+    #[test]
+    fn test_watch_accumulator() {
+        let program = program();
+        let mut debugger = Debugger::new(&program);
+        debugger.watch_acc(true);
+
+        assert_eq!(Event::Watch { pc: 1, old: 0, new: 1 }, debugger.resume());
+        assert_eq!(Event::Watch { pc: 6, old: 1, new: 2 }, debugger.resume());
+        assert_eq!(Event::Watch { pc: 3, old: 2, new: 5 }, debugger.resume());
+        assert_eq!(Event::Exit(ExitState::InfiniteLoop(5)), debugger.resume());
+    }
+
+    #[test]
+    fn test_trace_and_loop_cycle() {
+        let program = program();
+        let mut debugger = Debugger::new(&program);
+
+        assert_eq!(Event::Exit(ExitState::InfiniteLoop(5)), debugger.resume());
+
+        let pcs = debugger.trace().iter().map(|entry| entry.pc).collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 2, 6, 7, 3, 4], pcs);
+
+        let entry = &debugger.trace()[5];
+        assert_eq!((&Instruction::Acc(3), 2, 5), (entry.instruction, entry.acc_before, entry.acc_after));
+
+        let cycle = debugger.loop_cycle().unwrap().iter().map(|entry| entry.pc).collect::<Vec<_>>();
+        assert_eq!(vec![1, 2, 6, 7, 3, 4], cycle);
+    }
+}
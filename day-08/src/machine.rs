@@ -0,0 +1,299 @@
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    ops::{Index, IndexMut},
+};
+
+use crate::Instruction;
+
+/// The number of registers of the machine
+pub const NUM_REGISTERS: usize = 4;
+
+/// The index of the accumulator register
+pub const ACC: usize = 0;
+
+/// The registers of the machine, the first one is the accumulator
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registers([i64; NUM_REGISTERS]);
+
+impl Registers {
+    pub fn acc(&self) -> i64 {
+        self.0[ACC]
+    }
+}
+
+impl Index<usize> for Registers {
+    type Output = i64;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Registers {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+/// Tells the machine where to continue after an instruction was executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Continue with the next instruction
+    Next,
+    /// Move the program counter by the given offset
+    Jump(i64),
+    /// Stop the machine
+    Halt,
+}
+
+/// A single operation of an instruction set.
+///
+/// New opcodes are added by implementing this trait for another instruction type,
+/// the `Machine` runs any program of `Operation`s without further changes.
+pub trait Operation: Debug {
+    fn execute(&self, registers: &mut Registers) -> Flow;
+}
+
+impl Operation for Instruction {
+    fn execute(&self, registers: &mut Registers) -> Flow {
+        match self {
+            Instruction::Acc(value) => {
+                registers[ACC] += value;
+                Flow::Next
+            }
+            Instruction::Jmp(offset) => Flow::Jump(*offset),
+            Instruction::Nop(_) => Flow::Next,
+        }
+    }
+}
+
+/// Boot code with a halt in place of a single instruction, stops the program at that position
+#[derive(Debug)]
+pub enum Patched<'a> {
+    Boot(&'a Instruction),
+    Halt,
+}
+
+impl Operation for Patched<'_> {
+    fn execute(&self, registers: &mut Registers) -> Flow {
+        match self {
+            Patched::Boot(instruction) => instruction.execute(registers),
+            Patched::Halt => Flow::Halt,
+        }
+    }
+}
+
+/// Returns the program with the instruction at `pc` replaced by a halt
+pub fn halt_at(program: &[Instruction], pc: usize) -> Vec<Patched<'_>> {
+    program
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| if index == pc { Patched::Halt } else { Patched::Boot(instruction) })
+        .collect()
+}
+
+/// The state the machine exited with, every variant holds the accumulator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExitState {
+    /// The instruction right after the last one was reached
+    Success(i64),
+    /// An instruction was about to be executed a second time
+    InfiniteLoop(i64),
+    /// The program counter points outside of the program
+    OutOfBounds { pc: i64, acc: i64 },
+    /// The maximum number of steps was executed
+    StepLimit(i64),
+    /// An instruction stopped the machine
+    Halted(i64),
+}
+
+/// The machine that runs a program
+#[derive(Debug)]
+pub struct Machine<'a, I: Operation = Instruction> {
+    program: &'a [I],
+    registers: Registers,
+    pc: i64,
+    steps: usize,
+    step_limit: Option<usize>,
+    visited: HashSet<i64>,
+    halted: bool,
+}
+
+impl<'a, I: Operation> Machine<'a, I> {
+    pub fn new(program: &'a [I]) -> Self {
+        Self {
+            program,
+            registers: Registers::default(),
+            pc: 0,
+            steps: 0,
+            step_limit: None,
+            visited: HashSet::new(),
+            halted: false,
+        }
+    }
+
+    /// Limits the number of instructions the machine executes
+    pub fn with_step_limit(mut self, limit: usize) -> Self {
+        self.step_limit = Some(limit);
+        self
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    pub fn acc(&self) -> i64 {
+        self.registers.acc()
+    }
+
+    pub fn pc(&self) -> i64 {
+        self.pc
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Returns the instruction the program counter points to
+    pub fn current(&self) -> Option<&'a I> {
+        if self.pc < 0 {
+            return None;
+        }
+        self.program.get(self.pc as usize)
+    }
+
+    /// Executes a single instruction, returns the exit state once the machine stops
+    pub fn step(&mut self) -> Option<ExitState> {
+        let acc = self.acc();
+
+        if self.halted {
+            return Some(ExitState::Halted(acc));
+        }
+        if self.step_limit.is_some_and(|limit| self.steps >= limit) {
+            return Some(ExitState::StepLimit(acc));
+        }
+        if self.pc == self.program.len() as i64 {
+            return Some(ExitState::Success(acc));
+        }
+        if self.pc < 0 || self.pc > self.program.len() as i64 {
+            return Some(ExitState::OutOfBounds { pc: self.pc, acc });
+        }
+        if !self.visited.insert(self.pc) {
+            return Some(ExitState::InfiniteLoop(acc));
+        }
+
+        let flow = self.program[self.pc as usize].execute(&mut self.registers);
+        self.steps += 1;
+
+        match flow {
+            Flow::Next => self.pc += 1,
+            Flow::Jump(offset) => self.pc += offset,
+            Flow::Halt => {
+                self.halted = true;
+                return Some(ExitState::Halted(self.acc()));
+            }
+        }
+
+        None
+    }
+
+    /// Runs the program until the machine stops
+    pub fn run(&mut self) -> ExitState {
+        loop {
+            if let Some(state) = self.step() {
+                return state;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ACC, ExitState, Flow, Machine, Operation, Registers, halt_at};
+    use crate::Instruction;
+
+    /// An instruction set that extends the boot code with a few more opcodes
+    #[derive(Debug)]
+    enum Extended {
+        Boot(Instruction),
+        Mov(usize, i64),
+        Add(usize, usize),
+        Hlt,
+    }
+
+    impl Operation for Extended {
+        fn execute(&self, registers: &mut Registers) -> Flow {
+            match self {
+                Extended::Boot(instruction) => instruction.execute(registers),
+                Extended::Mov(register, value) => {
+                    registers[*register] = *value;
+                    Flow::Next
+                }
+                Extended::Add(target, source) => {
+                    registers[*target] += registers[*source];
+                    Flow::Next
+                }
+                Extended::Hlt => Flow::Halt,
+            }
+        }
+    }
+
+    #[test]
+    fn test_machine_steps() {
+        let program = vec![Instruction::Acc(3), Instruction::Jmp(2), Instruction::Acc(10), Instruction::Nop(0)];
+        let mut machine = Machine::new(&program);
+
+        assert_eq!(None, machine.step());
+        assert_eq!((1, 3), (machine.pc(), machine.acc()));
+        assert_eq!(None, machine.step());
+        assert_eq!(3, machine.pc());
+        assert_eq!(None, machine.step());
+        assert_eq!(Some(ExitState::Success(3)), machine.step());
+        assert_eq!(3, machine.steps());
+    }
+
+    #[test]
+    fn test_machine_out_of_bounds() {
+        let program = vec![Instruction::Acc(1), Instruction::Jmp(5)];
+        assert_eq!(ExitState::OutOfBounds { pc: 6, acc: 1 }, Machine::new(&program).run());
+
+        let program = vec![Instruction::Jmp(-1)];
+        assert_eq!(ExitState::OutOfBounds { pc: -1, acc: 0 }, Machine::new(&program).run());
+    }
+
+    #[test]
+    fn test_machine_step_limit() {
+        let program = vec![Instruction::Acc(1), Instruction::Acc(1), Instruction::Acc(1)];
+        assert_eq!(ExitState::StepLimit(2), Machine::new(&program).with_step_limit(2).run());
+    }
+
+    #[test]
+    fn test_machine_halt_at() {
+        let program = vec![Instruction::Acc(1), Instruction::Acc(2), Instruction::Jmp(-2)];
+        assert_eq!(ExitState::Halted(1), Machine::new(&halt_at(&program, 1)).run());
+        assert_eq!(ExitState::InfiniteLoop(3), Machine::new(&halt_at(&program, 5)).run());
+    }
+
+    #[test]
+    fn test_machine_detects_loop_to_first_instruction() {
+        let program = vec![Instruction::Acc(2), Instruction::Jmp(-1)];
+        assert_eq!(ExitState::InfiniteLoop(2), Machine::new(&program).run());
+    }
+
+    #[test]
+    fn test_machine_extended_instruction_set() {
+        let program = vec![
+            Extended::Mov(1, 20),
+            Extended::Boot(Instruction::Acc(22)),
+            Extended::Add(ACC, 1),
+            Extended::Hlt,
+            Extended::Boot(Instruction::Acc(100)),
+        ];
+        let mut machine = Machine::new(&program);
+
+        assert_eq!(ExitState::Halted(42), machine.run());
+        assert_eq!(20, machine.registers()[1]);
+        assert_eq!(ExitState::Halted(42), machine.run());
+    }
+}
@@ -1,8 +1,15 @@
+mod analysis;
+mod assembler;
+mod debugger;
+mod machine;
+mod repair;
+
 use anyhow::anyhow;
+use std::fmt::{self, Display};
 
-use std::collections::HashSet;
+use machine::{ExitState, Machine};
 
-///
+/// A single instruction of the boot code
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum Instruction {
     Acc(i64),
@@ -10,10 +17,14 @@ enum Instruction {
     Jmp(i64),
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum ExitState {
-    Success(i64),
-    InfiniteLoop(i64),
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Acc(value) => write!(f, "acc {:+}", value),
+            Instruction::Jmp(value) => write!(f, "jmp {:+}", value),
+            Instruction::Nop(value) => write!(f, "nop {:+}", value),
+        }
+    }
 }
 
 peg::parser!{
@@ -33,45 +44,15 @@ fn parse_line(line: &str) -> anyhow::Result<Instruction> {
 }
 
 /// Run the given instructions
-fn run_instructions(instructions: &[Instruction]) -> anyhow::Result<ExitState> {
-    let mut acc = 0;
-    let mut cursor: i64 = 0;
-    let mut visited = HashSet::<i64>::new();
-
-    loop {
-        if let Some(instruction) = instructions.get(cursor as usize) {
-            match instruction {
-                Instruction::Acc(a) => acc += a,
-                Instruction::Jmp(jmp) => cursor += jmp - 1,
-                Instruction::Nop(_) => (),
-            }
-        } else {
-            return Err(anyhow!("No instruction found"));
-        }
-
-        cursor += 1;
-        if !visited.insert(cursor) { return Ok(ExitState::InfiniteLoop(acc)) }
-        if cursor == instructions.len() as i64 { return Ok(ExitState::Success(acc)) }
-    }
+fn run_instructions(instructions: &[Instruction]) -> ExitState {
+    Machine::new(instructions).run()
 }
 
+/// Flip a single `jmp` or `nop` instruction so the program terminates, returns the accumulator
 fn run_instructions_switch(instructions: &[Instruction]) -> anyhow::Result<i64> {
-    for (index, instruction) in instructions.iter().enumerate() {
-        let mut copy = instructions.to_vec();
-
-        match instruction {
-            Instruction::Nop(v) if *v != 0 => copy[index] = Instruction::Jmp(*v),
-            Instruction::Jmp(v) => copy[index] = Instruction::Nop(*v),
-            _ => continue,
-        };
-
-        match run_instructions(&copy) {
-            Ok(ExitState::Success(v)) => return Ok(v),
-            _ => continue,
-        }
-    }
-
-    Err(anyhow!("No switched line found"))
+    repair::find_repair(instructions)
+        .map(|repair| repair.acc)
+        .ok_or_else(|| anyhow!("No switched line found"))
 }
 
 fn main() -> anyhow::Result<()> {
@@ -81,11 +62,47 @@ fn main() -> anyhow::Result<()> {
         .filter_map(Result::ok)
         .collect::<Vec<_>>();
 
-    let count = run_instructions(&instructions)?;
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("--trace") => {
+            debugger::print_trace(&instructions);
+            return Ok(());
+        }
+        Some("--debug") => return debugger::interactive(&instructions),
+        Some("--analyze") => {
+            println!("{}", analysis::analyze(&instructions));
+            return Ok(());
+        }
+        Some("--disassemble") => {
+            print!("{}", assembler::disassemble(&instructions));
+            return Ok(());
+        }
+        Some("--step-limit") => {
+            let limit = args.next().ok_or_else(|| anyhow!("Missing step limit"))?.parse()?;
+            println!("{:?}", Machine::new(&instructions).with_step_limit(limit).run());
+            return Ok(());
+        }
+        Some("--halt-at") => {
+            let pc = args.next().ok_or_else(|| anyhow!("Missing instruction index"))?.parse()?;
+            println!("{:?}", Machine::new(&machine::halt_at(&instructions, pc)).run());
+            return Ok(());
+        }
+        Some("--assemble") => {
+            let path = args.next().ok_or_else(|| anyhow!("Missing assembly file"))?;
+            let program = assembler::assemble(&std::fs::read_to_string(path)?)?;
+            print!("{}", assembler::disassemble(&program));
+            println!("{:?}", run_instructions(&program));
+            return Ok(());
+        }
+        _ => (),
+    }
+
+    let count = run_instructions(&instructions);
     dbg!(&count);
     assert_eq!(ExitState::InfiniteLoop(1584), count);
 
     dbg!(run_instructions_switch(&instructions)?);
+    dbg!(repair::find_all_repairs(&instructions));
 
     Ok(())
 }
@@ -126,7 +143,7 @@ mod tests {
             acc +6
         "#);
 
-        assert_eq!(ExitState::InfiniteLoop(5), run_instructions(&input).unwrap());
+        assert_eq!(ExitState::InfiniteLoop(5), run_instructions(&input));
     }
 
     #[test]
@@ -0,0 +1,199 @@
+use crate::Instruction;
+
+/// A single flipped instruction that lets the program terminate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    /// The index of the changed instruction
+    pub index: usize,
+    /// The instruction that replaces the original one
+    pub instruction: Instruction,
+    /// The accumulator after the repaired program terminated
+    pub acc: i64,
+}
+
+/// Returns the index of the instruction that is executed after the given one
+fn successor(index: usize, instruction: &Instruction) -> i64 {
+    match instruction {
+        Instruction::Jmp(offset) => index as i64 + offset,
+        _ => index as i64 + 1,
+    }
+}
+
+/// Returns the instruction with `jmp` and `nop` switched
+fn flip(instruction: &Instruction) -> Option<Instruction> {
+    match instruction {
+        Instruction::Jmp(v) => Some(Instruction::Nop(*v)),
+        Instruction::Nop(v) => Some(Instruction::Jmp(*v)),
+        Instruction::Acc(_) => None,
+    }
+}
+
+/// Every instruction has a single successor, therefore all instructions that terminate
+/// form a tree rooted at the end of the program when following the edges in reverse.
+struct Reachability {
+    /// Entry and exit time of the depth first search, `None` if the instruction never terminates
+    times: Vec<Option<(usize, usize)>>,
+    /// The accumulator added from the instruction on until the program terminates
+    acc_to_end: Vec<i64>,
+}
+
+impl Reachability {
+    fn new(instructions: &[Instruction]) -> Self {
+        let end = instructions.len();
+
+        let mut predecessors = vec![Vec::new(); end + 1];
+        for (index, instruction) in instructions.iter().enumerate() {
+            let next = successor(index, instruction);
+            if (0..=end as i64).contains(&next) {
+                predecessors[next as usize].push(index);
+            }
+        }
+
+        let mut times = vec![None; end + 1];
+        let mut acc_to_end = vec![0; end + 1];
+        let mut time = 0;
+        let mut stack = vec![(end, false)];
+
+        while let Some((node, visited)) = stack.pop() {
+            if visited {
+                times[node] = times[node].map(|(entry, _)| (entry, time));
+                time += 1;
+                continue;
+            }
+
+            times[node] = Some((time, 0));
+            time += 1;
+            stack.push((node, true));
+
+            for &previous in &predecessors[node] {
+                let acc = match instructions[previous] {
+                    Instruction::Acc(value) => value,
+                    _ => 0,
+                };
+                acc_to_end[previous] = acc + acc_to_end[node];
+                stack.push((previous, false));
+            }
+        }
+
+        Self { times, acc_to_end }
+    }
+
+    /// Returns true if the program terminates when started at the given index
+    fn terminates(&self, index: i64) -> bool {
+        self.times(index).is_some()
+    }
+
+    /// Returns true if the program started at `index` runs through `via` before it terminates
+    fn passes(&self, index: i64, via: usize) -> bool {
+        match (self.times(index), self.times[via]) {
+            (Some((entry, exit)), Some((via_entry, via_exit))) => via_entry <= entry && exit <= via_exit,
+            _ => false,
+        }
+    }
+
+    fn times(&self, index: i64) -> Option<(usize, usize)> {
+        if index < 0 {
+            return None;
+        }
+        self.times.get(index as usize).copied().flatten()
+    }
+}
+
+/// Returns the executed instructions of the unmodified program in order,
+/// together with the accumulator before each of them
+fn execution_path(instructions: &[Instruction]) -> Vec<(usize, i64)> {
+    let mut visited = vec![false; instructions.len()];
+    let mut path = Vec::new();
+    let mut index = 0_i64;
+    let mut acc = 0;
+
+    while let Some(instruction) = instructions.get(index as usize).filter(|_| index >= 0) {
+        if std::mem::replace(&mut visited[index as usize], true) {
+            break;
+        }
+        path.push((index as usize, acc));
+        if let Instruction::Acc(value) = instruction {
+            acc += value;
+        }
+        index = successor(index as usize, instruction);
+    }
+
+    path
+}
+
+/// Lazily yields all single flips along the execution path that let the program terminate
+fn repairs(instructions: &[Instruction]) -> impl Iterator<Item = Repair> + '_ {
+    let reachability = Reachability::new(instructions);
+
+    execution_path(instructions)
+        .into_iter()
+        .filter_map(move |(index, acc)| {
+            let instruction = flip(&instructions[index])?;
+            let next = successor(index, &instruction);
+
+            if reachability.terminates(next) && !reachability.passes(next, index) {
+                Some(Repair { index, instruction, acc: acc + reachability.acc_to_end[next as usize] })
+            } else {
+                None
+            }
+        })
+}
+
+/// Finds the first instruction to flip so the program terminates, in linear time
+pub fn find_repair(instructions: &[Instruction]) -> Option<Repair> {
+    repairs(instructions).next()
+}
+
+/// Finds all single flips that let the program terminate
+pub fn find_all_repairs(instructions: &[Instruction]) -> Vec<Repair> {
+    repairs(instructions).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Repair, find_all_repairs, find_repair};
+    use crate::{Instruction, Instruction::*, machine::{ExitState, Machine}};
+
+    fn brute_force(instructions: &[Instruction]) -> Vec<(usize, i64)> {
+        (0..instructions.len())
+            .filter_map(|index| {
+                let mut copy = instructions.to_vec();
+                copy[index] = match copy[index] {
+                    Jmp(v) => Nop(v),
+                    Nop(v) => Jmp(v),
+                    Acc(_) => return None,
+                };
+                match Machine::new(&copy).run() {
+                    ExitState::Success(acc) => Some((index, acc)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_repair() {
+        let input = vec![Nop(0), Acc(1), Jmp(4), Acc(3), Jmp(-3), Acc(-99), Acc(1), Jmp(-4), Acc(6)];
+        assert_eq!(Some(Repair { index: 7, instruction: Nop(-4), acc: 8 }), find_repair(&input));
+    }
+
+    #[test]
+    fn test_find_all_repairs_matches_brute_force() {
+        let programs = vec![
+            vec![Nop(0), Acc(1), Jmp(4), Acc(3), Jmp(-3), Acc(-99), Acc(1), Jmp(-4), Acc(6)],
+            vec![Nop(2), Jmp(0), Acc(5), Jmp(-1), Acc(7)],
+            vec![Acc(1), Nop(3), Jmp(2), Acc(4), Nop(-2), Jmp(-5)],
+            vec![Acc(2), Jmp(2), Acc(10), Nop(0)],
+            vec![Jmp(2), Acc(3), Nop(-2), Jmp(-1)],
+        ];
+
+        for program in programs {
+            let mut repairs = find_all_repairs(&program)
+                .into_iter()
+                .map(|repair| (repair.index, repair.acc))
+                .collect::<Vec<_>>();
+            repairs.sort_unstable();
+            assert_eq!(brute_force(&program), repairs, "{:?}", program);
+        }
+    }
+}
@@ -1,5 +1,5 @@
-use FoldWhile::{Continue, Done};
-use itertools::{FoldWhile, Itertools};
+use itertools::Itertools;
+use std::collections::{HashMap, VecDeque};
 
 fn parse(lines: &str) -> Vec<u64> {
     lines
@@ -10,51 +10,112 @@ fn parse(lines: &str) -> Vec<u64> {
         .collect::<Vec<_>>()
 }
 
-fn find_sums(sum: u64, preamble: &[u64]) -> Vec<(u64, u64)> {
-    preamble
-        .iter()
-        .tuple_combinations()
-        .filter(|(left, right)| left != right)
-        .filter(|(left, right)| *left + *right == sum)
-        .map(|(x, y)| (*x, *y))
-        .collect::<Vec<_>>()
+/// Holds the last numbers of the XMAS data stream together with a count per value,
+/// to check whether a number is the sum of two of them in linear time.
+#[derive(Debug)]
+struct XmasStream {
+    preamble: usize,
+    window: VecDeque<u64>,
+    counts: HashMap<u64, usize>,
 }
 
-fn find_first_number(preamble: usize, numbers: &[u64]) -> Option<u64> {
+impl XmasStream {
+    pub fn new(preamble: usize) -> Self {
+        Self {
+            preamble,
+            window: VecDeque::with_capacity(preamble + 1),
+            counts: HashMap::with_capacity(preamble + 1),
+        }
+    }
+
+    /// Returns all pairs of two different numbers in the window that add up to `sum`
+    pub fn find_sums(&self, sum: u64) -> Vec<(u64, u64)> {
+        self.window
+            .iter()
+            .filter(|&&left| left < sum && left < sum - left)
+            .filter(|&&left| self.counts.contains_key(&(sum - left)))
+            .map(|&left| (left, sum - left))
+            .collect::<Vec<_>>()
+    }
+
+    /// Returns true if the number is the sum of two different numbers of the full window
+    pub fn is_valid(&self, value: u64) -> bool {
+        self.window.len() == self.preamble && !self.find_sums(value).is_empty()
+    }
+
+    /// Adds the number to the window, returns whether it is valid once the preamble is read
+    pub fn push(&mut self, value: u64) -> Option<bool> {
+        let valid = if self.window.len() == self.preamble {
+            Some(self.is_valid(value))
+        } else {
+            None
+        };
+
+        self.window.push_back(value);
+        *self.counts.entry(value).or_insert(0) += 1;
+
+        if self.window.len() > self.preamble {
+            if let Some(first) = self.window.pop_front() {
+                if let Some(count) = self.counts.get_mut(&first) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.counts.remove(&first);
+                    }
+                }
+            }
+        }
+
+        valid
+    }
+}
+
+/// Lazily returns all numbers of the stream that are not the sum of two of the previous numbers
+fn find_invalid_numbers<I>(preamble: usize, numbers: I) -> impl Iterator<Item = u64>
+where
+    I: IntoIterator<Item = u64>,
+{
+    let mut stream = XmasStream::new(preamble);
     numbers
-        .iter()
-        .skip(preamble)
-        .enumerate()
-        .find(|(index, &sum)| {
-            find_sums(sum, &numbers[*index..index + preamble]).is_empty()
-        })
-        .map(|s| *s.1)
+        .into_iter()
+        .filter(move |&number| stream.push(number) == Some(false))
+}
+
+fn find_first_number(preamble: usize, numbers: &[u64]) -> Option<u64> {
+    find_invalid_numbers(preamble, numbers.iter().copied()).next()
 }
 
+/// Finds at least two contiguous numbers that add up to `sum` with two pointers
 fn find_contiguous_numbers(sum: u64, numbers: &[u64]) -> Option<Vec<u64>> {
-    numbers
-        .iter()
-        .batching(|iter| {
-            let value = iter.clone().fold_while(vec![], |mut acc, &number| {
-                acc.push(number);
-                if acc.iter().sum::<u64>() < sum {
-                    Continue(acc)
-                } else {
-                    Done(acc)
-                }
-            }).into_inner();
-            iter.next();
-            Some(value)
-        })
-        .find(|list| list.iter().sum::<u64>() == sum)
+    let mut start = 0;
+    let mut total = 0;
+
+    for end in 0..numbers.len() {
+        total += numbers[end];
+        while total > sum {
+            total -= numbers[start];
+            start += 1;
+        }
+        if total == sum && end > start {
+            return Some(numbers[start..=end].to_vec());
+        }
+    }
+
+    None
 }
 
 fn main() {
     let numbers = parse(include_str!("numbers.txt"));
+    let preamble = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse::<usize>().ok())
+        .unwrap_or(25);
 
-    let number = find_first_number(25, &numbers).unwrap();
+    let number = find_first_number(preamble, &numbers).unwrap();
     dbg!(number);
 
+    let invalid = find_invalid_numbers(preamble, numbers.iter().copied()).count();
+    dbg!(invalid);
+
     let list = find_contiguous_numbers(number, &numbers).unwrap();
     let (min, max) = list.iter().minmax().into_option().unwrap();
 
@@ -64,11 +125,26 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::{find_contiguous_numbers, find_first_number, find_sums};
+    use crate::{XmasStream, find_contiguous_numbers, find_first_number, find_invalid_numbers};
 
     #[test]
     fn test_find_sums_of_pairs() {
-        assert_eq!(vec![(2, 5), (3, 4)], find_sums(7, &[1, 2, 3, 4, 5]));
+        let mut stream = XmasStream::new(5);
+        for number in 1..=5 {
+            stream.push(number);
+        }
+        assert_eq!(vec![(2, 5), (3, 4)], stream.find_sums(7));
+        assert_eq!(Vec::<(u64, u64)>::new(), stream.find_sums(2));
+    }
+
+    #[test]
+    fn test_stream_moves_window() {
+        let mut stream = XmasStream::new(2);
+        assert_eq!(None, stream.push(10));
+        assert_eq!(None, stream.push(10));
+        assert_eq!(Some(false), stream.push(20));
+        assert_eq!(Some(true), stream.push(30));
+        assert_eq!(Some(false), stream.push(30));
     }
 
     #[test]
@@ -79,6 +155,14 @@ mod tests {
         assert_eq!(Some(127), find_first_number(5, &numbers));
     }
 
+    #[test]
+    fn test_find_all_invalid_numbers() {
+        let numbers: Vec<u64> = vec![
+            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576, 1
+        ];
+        assert_eq!(vec![127, 1], find_invalid_numbers(5, numbers).collect::<Vec<_>>());
+    }
+
     #[test]
     fn test_find_contiguous_numbers() {
         let numbers: Vec<u64> = vec![
@@ -1,4 +1,7 @@
 use itertools::Itertools;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use std::collections::HashSet;
 
 fn find_differences(adapters: &[u64]) -> Vec<u64> {
     let highest = adapters.iter().max().unwrap();
@@ -18,18 +21,203 @@ fn find_distribution(adapters: &[u64]) -> (u64, u64) {
     (joltage_1, joltage_3)
 }
 
-fn calculate_arrangements(adapters: &[u64]) -> u64 {
-    find_differences(adapters)
-        .into_iter()
-        .group_by(|&key| key == 1)
-        .into_iter()
-        .filter(|(key, _)| *key)
-        .map(|(_, group)| {
-            let count = group.count() as u64;
-            let extra = (count - 1) / 3;
-            2u64.pow(count as u32 - 1) - extra
-        })
-        .product::<u64>()
+/// All valid chains of adapters that connect the outlet to the device.
+///
+/// Each adapter accepts an input of up to `max_step` jolts lower than its rating, the device is rated
+/// `max_step` jolts higher than the highest adapter. Adapters with equal ratings are counted once.
+/// A chain lists the joltages of the used adapters, without the outlet and the device.
+#[derive(Debug, Clone)]
+struct AdapterChains {
+    /// The outlet followed by the sorted adapters
+    joltages: Vec<u64>,
+    max_step: u64,
+    required: HashSet<u64>,
+    forbidden: HashSet<u64>,
+}
+
+impl AdapterChains {
+    pub fn new(adapters: &[u64], max_step: u64) -> Self {
+        let joltages = std::iter::once(0)
+            .chain(adapters.iter().cloned().sorted())
+            .dedup()
+            .collect_vec();
+
+        Self {
+            joltages,
+            max_step,
+            required: HashSet::new(),
+            forbidden: HashSet::new(),
+        }
+    }
+
+    /// Only allows chains that use the given adapter
+    pub fn require(mut self, joltage: u64) -> Self {
+        self.required.insert(joltage);
+        self
+    }
+
+    /// Only allows chains that do not use the given adapter
+    pub fn forbid(mut self, joltage: u64) -> Self {
+        self.forbidden.insert(joltage);
+        self
+    }
+
+    fn end(&self) -> usize {
+        self.joltages.len() - 1
+    }
+
+    /// Returns the indices of all adapters that can follow the adapter at `index`
+    fn next(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let joltage = self.joltages[index];
+        let mut skipped_required = false;
+
+        (index + 1..self.joltages.len())
+            .take_while(move |&next| self.joltages[next] - joltage <= self.max_step)
+            .take_while(move |&next| {
+                let allowed = !skipped_required;
+                skipped_required |= self.required.contains(&self.joltages[next]);
+                allowed
+            })
+            .filter(move |&next| !self.forbidden.contains(&self.joltages[next]))
+    }
+
+    /// The number of chains from every adapter to the device
+    fn ways_to_end(&self) -> Vec<BigUint> {
+        let mut ways = vec![BigUint::zero(); self.joltages.len()];
+        if self.forbidden.contains(&self.joltages[self.end()]) || self.required.iter().any(|j| !self.joltages.contains(j)) {
+            return ways;
+        }
+
+        ways[self.end()] = BigUint::one();
+        for index in (0..self.end()).rev() {
+            ways[index] = self.next(index).map(|next| &ways[next]).sum();
+        }
+        ways
+    }
+
+    /// The number of chains from the outlet to every adapter
+    fn ways_from_start(&self, ways_to_end: &[BigUint]) -> Vec<BigUint> {
+        let mut ways = vec![BigUint::zero(); self.joltages.len()];
+        ways[0] = BigUint::one();
+
+        for index in 0..self.end() {
+            if ways_to_end[index].is_zero() {
+                continue;
+            }
+            let current = ways[index].clone();
+            for next in self.next(index) {
+                ways[next] += &current;
+            }
+        }
+        ways
+    }
+
+    /// Counts all valid chains with dynamic programming
+    pub fn count(&self) -> BigUint {
+        self.ways_to_end().swap_remove(0)
+    }
+
+    /// Lazily enumerates all valid chains
+    pub fn iter(&self) -> Chains<'_> {
+        let ways_to_end = self.ways_to_end();
+        let stack = if ways_to_end[0].is_zero() { vec![] } else { vec![vec![0]] };
+        Chains { chains: self, ways_to_end, stack }
+    }
+
+    /// Finds the chain with the fewest (`longest == false`) or the most adapters
+    fn extreme(&self, longest: bool) -> Option<Vec<u64>> {
+        let ways_to_end = self.ways_to_end();
+        if ways_to_end[0].is_zero() {
+            return None;
+        }
+
+        // the number of adapters to the device together with the following adapter
+        let mut best: Vec<Option<(usize, usize)>> = vec![None; self.joltages.len()];
+        best[self.end()] = Some((0, self.end()));
+
+        for index in (0..self.end()).rev() {
+            let candidates = self
+                .next(index)
+                .filter_map(|next| best[next].map(|(length, _)| (length + 1, next)));
+            best[index] = if longest {
+                candidates.max_by_key(|(length, _)| *length)
+            } else {
+                candidates.min_by_key(|(length, _)| *length)
+            };
+        }
+
+        let mut chain = Vec::new();
+        let mut index = 0;
+        while let Some((_, next)) = best[index].filter(|_| index != self.end()) {
+            chain.push(self.joltages[next]);
+            index = next;
+        }
+        Some(chain)
+    }
+
+    pub fn shortest(&self) -> Option<Vec<u64>> {
+        self.extreme(false)
+    }
+
+    pub fn longest(&self) -> Option<Vec<u64>> {
+        self.extreme(true)
+    }
+
+    /// Returns all adapters that are part of every valid chain
+    pub fn mandatory(&self) -> Vec<u64> {
+        let ways_to_end = self.ways_to_end();
+        let total = &ways_to_end[0];
+        if total.is_zero() {
+            return vec![];
+        }
+
+        let ways_from_start = self.ways_from_start(&ways_to_end);
+        (1..self.joltages.len())
+            .filter(|&index| &(&ways_from_start[index] * &ways_to_end[index]) == total)
+            .map(|index| self.joltages[index])
+            .collect()
+    }
+}
+
+/// Lazy depth first iterator over all valid adapter chains, in ascending order
+struct Chains<'a> {
+    chains: &'a AdapterChains,
+    ways_to_end: Vec<BigUint>,
+    stack: Vec<Vec<usize>>,
+}
+
+impl<'a> Iterator for Chains<'a> {
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(path) = self.stack.pop() {
+            let last = *path.last()?;
+            if last == self.chains.end() {
+                return Some(path[1..].iter().map(|&index| self.chains.joltages[index]).collect());
+            }
+
+            let nexts = self
+                .chains
+                .next(last)
+                .filter(|&next| !self.ways_to_end[next].is_zero())
+                .collect_vec();
+            for next in nexts.into_iter().rev() {
+                let mut extended = path.clone();
+                extended.push(next);
+                self.stack.push(extended);
+            }
+        }
+        None
+    }
+}
+
+/// Counts all arrangements of adapters that connect the outlet to the device with dynamic programming
+fn count_arrangements(adapters: &[u64], max_step: u64) -> BigUint {
+    AdapterChains::new(adapters, max_step).count()
+}
+
+fn calculate_arrangements(adapters: &[u64]) -> BigUint {
+    count_arrangements(adapters, 3)
 }
 
 fn parse(input: &str) -> Vec<u64> {
@@ -46,12 +234,33 @@ fn main() {
     dbg!(left * right);
 
     let count = calculate_arrangements(&adapters);
-    dbg!(&count);
+    dbg!(count.to_string());
+
+    let mut chains = AdapterChains::new(&adapters, 3);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next().and_then(|joltage| joltage.parse().ok())) {
+            ("--require", Some(joltage)) => chains = chains.require(joltage),
+            ("--forbid", Some(joltage)) => chains = chains.forbid(joltage),
+            _ => (),
+        }
+    }
+
+    dbg!(chains.count().to_string());
+    dbg!(chains.shortest().map(|chain| chain.len()));
+    dbg!(chains.longest().map(|chain| chain.len()));
+    dbg!(chains.mandatory().len());
+
+    for chain in chains.iter().take(3) {
+        println!("{}", chain.iter().join(" "));
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{calculate_arrangements, find_differences, find_distribution};
+    use crate::{AdapterChains, calculate_arrangements, count_arrangements, find_differences, find_distribution};
+    use itertools::Itertools;
+    use num_bigint::BigUint;
 
     #[test]
     fn test_jolt_differences() {
@@ -82,11 +291,11 @@ mod tests {
 
     #[test]
     fn test_calculate_arrangments() {
-        assert_eq!(2, calculate_arrangements(&[0, 3, 4, 5, 8]));
-        assert_eq!(4, calculate_arrangements(&[0, 3, 4, 5, 6, 9]));
-        assert_eq!(7, calculate_arrangements(&[0, 3, 4, 5, 6, 7, 10]));
-        assert_eq!(15, calculate_arrangements(&[0, 3, 4, 5, 6, 7, 8, 11]));
-        assert_eq!(8, calculate_arrangements(&[1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]));
+        assert_eq!(BigUint::from(2u64), calculate_arrangements(&[0, 3, 4, 5, 8]));
+        assert_eq!(BigUint::from(4u64), calculate_arrangements(&[0, 3, 4, 5, 6, 9]));
+        assert_eq!(BigUint::from(7u64), calculate_arrangements(&[0, 3, 4, 5, 6, 7, 10]));
+        assert_eq!(BigUint::from(13u64), calculate_arrangements(&[0, 3, 4, 5, 6, 7, 8, 11]));
+        assert_eq!(BigUint::from(8u64), calculate_arrangements(&[1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]));
     }
 
     #[test]
@@ -96,8 +305,71 @@ mod tests {
             8, 17, 7, 9, 4, 2, 34, 10, 3,
         ];
         assert_eq!(
-            19208,
+            BigUint::from(19208u64),
             calculate_arrangements(&adapters),
         )
     }
+
+    #[test]
+    fn test_count_arrangements_with_two_jolt_gaps() {
+        assert_eq!(BigUint::from(3u64), count_arrangements(&[1, 2, 4], 3));
+        assert_eq!(BigUint::from(5u64), count_arrangements(&[2, 3, 4, 6], 3));
+        assert_eq!(BigUint::from(0u64), count_arrangements(&[1, 5], 3));
+    }
+
+    #[test]
+    fn test_count_arrangements_with_other_max_steps() {
+        let adapters = (1..=10).collect::<Vec<_>>();
+        assert_eq!(BigUint::from(1u64), count_arrangements(&adapters, 1));
+        assert_eq!(BigUint::from(89u64), count_arrangements(&adapters, 2));
+        assert_eq!(BigUint::from(274u64), count_arrangements(&adapters, 3));
+        assert_eq!(BigUint::from(401u64), count_arrangements(&adapters, 4));
+    }
+
+    #[test]
+    fn test_count_arrangements_overflowing_u64() {
+        let adapters = (1..=100).collect::<Vec<_>>();
+        let expected = "180396380815100901214157639".parse::<BigUint>().unwrap();
+        assert_eq!(expected, count_arrangements(&adapters, 3));
+    }
+
+    #[test]
+    fn test_enumerate_chains() {
+        let chains = AdapterChains::new(&[16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4], 3);
+        let all = chains.iter().collect::<Vec<_>>();
+
+        assert_eq!(8, all.len());
+        assert_eq!(vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19], all[0]);
+        assert_eq!(vec![1, 4, 7, 10, 12, 15, 16, 19], all[7]);
+        assert_eq!(all.len(), chains.iter().unique().count());
+    }
+
+    #[test]
+    fn test_shortest_and_longest_chain() {
+        let chains = AdapterChains::new(&[16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4], 3);
+
+        assert_eq!(Some(vec![1, 4, 7, 10, 12, 15, 16, 19]), chains.shortest());
+        assert_eq!(Some(vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]), chains.longest());
+        assert_eq!(None, AdapterChains::new(&[1, 5], 3).shortest());
+    }
+
+    #[test]
+    fn test_required_and_forbidden_adapters() {
+        let adapters = [16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+
+        let chains = AdapterChains::new(&adapters, 3).require(5).forbid(11);
+        assert_eq!(BigUint::from(2u64), chains.count());
+        assert!(chains.iter().all(|chain| chain.contains(&5) && !chain.contains(&11)));
+        assert_eq!(2, chains.iter().count());
+
+        assert_eq!(BigUint::from(0u64), AdapterChains::new(&adapters, 3).forbid(4).count());
+        assert_eq!(BigUint::from(0u64), AdapterChains::new(&adapters, 3).require(2).count());
+    }
+
+    #[test]
+    fn test_mandatory_adapters() {
+        let chains = AdapterChains::new(&[16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4], 3);
+        assert_eq!(vec![1, 4, 7, 10, 12, 15, 16, 19], chains.mandatory());
+        assert_eq!(vec![1, 4, 5, 7, 10, 12, 15, 16, 19], chains.require(5).mandatory());
+    }
 }
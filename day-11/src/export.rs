@@ -0,0 +1,145 @@
+use std::{
+    convert::TryFrom,
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use gif::{Encoder, Frame, Repeat};
+
+use crate::{Seat, SeatPlan};
+
+/// The RGB colors of floor, empty and occupied seats
+const PALETTE: [u8; 9] = [
+    0x30, 0x30, 0x30,
+    0x40, 0xa0, 0x40,
+    0xd0, 0x30, 0x30,
+];
+
+fn color_index(seat: &Seat) -> u8 {
+    match seat {
+        Seat::Floor => 0,
+        Seat::Empty => 1,
+        Seat::Occupied => 2,
+    }
+}
+
+/// Returns the palette index of every pixel, each seat is a square of `scale` pixels
+fn pixels(plan: &SeatPlan, scale: usize) -> Vec<u8> {
+    plan.seats
+        .chunks(plan.width)
+        .flat_map(|row| {
+            let line = row
+                .iter()
+                .flat_map(|seat| std::iter::repeat_n(color_index(seat), scale))
+                .collect::<Vec<_>>();
+            std::iter::repeat_n(line, scale).flatten()
+        })
+        .collect()
+}
+
+/// Renders the seat plan as binary PPM image
+pub fn to_ppm(plan: &SeatPlan, scale: usize) -> Vec<u8> {
+    let mut image = format!("P6\n{} {}\n255\n", plan.width * scale, plan.height * scale).into_bytes();
+    for index in pixels(plan, scale) {
+        let offset = index as usize * 3;
+        image.extend_from_slice(&PALETTE[offset..offset + 3]);
+    }
+    image
+}
+
+/// Prints every generation into the terminal, replacing the previous one
+pub fn animate<I>(generations: I, delay: Duration) -> anyhow::Result<()>
+where
+    I: IntoIterator<Item = SeatPlan>,
+{
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    for (iteration, plan) in generations.into_iter().enumerate() {
+        write!(out, "\x1B[2J\x1B[H{}", plan)?;
+        writeln!(out, "Generation {}, occupied {}", iteration, plan.total_occupied())?;
+        out.flush()?;
+        thread::sleep(delay);
+    }
+
+    Ok(())
+}
+
+/// Writes every generation as PPM image into the directory, named `frame-0000.ppm` etc.
+pub fn write_ppm_frames<I, P>(generations: I, dir: P, scale: usize) -> anyhow::Result<()>
+where
+    I: IntoIterator<Item = SeatPlan>,
+    P: AsRef<Path>,
+{
+    fs::create_dir_all(&dir)?;
+
+    for (iteration, plan) in generations.into_iter().enumerate() {
+        let path = dir.as_ref().join(format!("frame-{:04}.ppm", iteration));
+        fs::write(path, to_ppm(&plan, scale))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the scaled size of the image, GIF images are at most 65535 pixels wide and high
+fn gif_size(size: usize, scale: usize) -> anyhow::Result<u16> {
+    size.checked_mul(scale)
+        .and_then(|size| u16::try_from(size).ok())
+        .ok_or_else(|| anyhow::anyhow!("Image size {} * {} exceeds {} pixels", size, scale, u16::MAX))
+}
+
+/// Writes all generations as animated GIF, `delay` is the time per frame in hundredths of a second
+pub fn write_gif<I, P>(generations: I, path: P, scale: usize, delay: u16) -> anyhow::Result<()>
+where
+    I: IntoIterator<Item = SeatPlan>,
+    P: AsRef<Path>,
+{
+    let mut generations = generations.into_iter().peekable();
+    let (width, height) = match generations.peek() {
+        Some(plan) => (gif_size(plan.width, scale)?, gif_size(plan.height, scale)?),
+        None => return Err(anyhow::anyhow!("No generations to export")),
+    };
+
+    let mut encoder = Encoder::new(File::create(path)?, width, height, &PALETTE)?;
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    for plan in generations {
+        let mut frame = Frame::from_indexed_pixels(width, height, &pixels(&plan, scale), None);
+        frame.delay = delay;
+        encoder.write_frame(&frame)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pixels, to_ppm, write_gif};
+    use crate::parse_seat_plan;
+
+    #[test]
+    fn test_pixels_are_scaled() {
+        let plan = parse_seat_plan("L.\n#L");
+        assert_eq!(vec![1, 1, 0, 0, 1, 1, 0, 0, 2, 2, 1, 1, 2, 2, 1, 1], pixels(&plan, 2));
+    }
+
+    #[test]
+    fn test_ppm_image() {
+        let plan = parse_seat_plan("L#");
+        let mut expected = b"P6\n2 1\n255\n".to_vec();
+        expected.extend_from_slice(&[0x40, 0xa0, 0x40, 0xd0, 0x30, 0x30]);
+        assert_eq!(expected, to_ppm(&plan, 1));
+    }
+
+    #[test]
+    fn test_gif_size_limit() {
+        let plan = parse_seat_plan("L.");
+        let path = std::env::temp_dir().join("day-11-gif-size-limit.gif");
+
+        assert!(write_gif(vec![plan], &path, 40_000, 10).is_err());
+        assert!(!path.exists());
+    }
+}
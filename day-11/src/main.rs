@@ -1,6 +1,9 @@
+mod export;
+
+use automaton::{Automaton, Neighborhood, Outcome, Rule};
 use std::fmt::Display;
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 enum Seat {
     Floor,
     Empty,
@@ -62,69 +65,163 @@ impl SeatPlan {
     /// ## Parameters
     /// * `occupied` - number of occupied seats to take into account to switch from occupied to empty
     /// * `steps` - number of steps to check in each direction, mostly 1 or longest grid dimension
+    #[cfg(test)]
     pub fn update(&self, occupied: u32, steps: u32) -> Self {
-        let mut new_plan = self.clone();
-
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let index = x + y * self.width;
-                let adjacent = self.adjacent(x as i64, y as i64, steps);
-
-                let seat = match &self.seats[index] {
-                    Seat::Empty => if adjacent == 0 { Seat::Occupied } else { Seat::Empty },
-                    Seat::Occupied => if adjacent >= occupied { Seat::Empty } else { Seat::Occupied },
-                    Seat::Floor => Seat::Floor,
-                };
-                new_plan.seats[index] = seat;
-            }
+        let mut seating = self.seating(occupied, steps);
+        seating.step();
+        self.with_seats(seating.into_cells())
+    }
+
+    /// Creates the automaton of the seating rules, the visible seats are precomputed once
+    pub fn seating(&self, occupied: u32, steps: u32) -> Automaton<Seat, SeatingRule> {
+        Automaton::new(self.seats.clone(), self.line_of_sight(steps), SeatingRule { occupied })
+    }
+
+    /// Returns all generations of the seat plan until the seats stabilize or repeat,
+    /// at most `GENERATION_LIMIT` generations
+    pub fn generations(&self, occupied: u32, steps: u32) -> Generations {
+        let seating = self.seating(occupied, steps);
+        let remaining = match seating.clone().run(GENERATION_LIMIT) {
+            Outcome::Stabilized { generation } => generation + 1,
+            Outcome::Oscillating { start, period } => start + period,
+            Outcome::LimitReached => GENERATION_LIMIT,
+        };
+
+        Generations {
+            plan: self.clone(),
+            seating,
+            remaining,
+            started: false,
+        }
+    }
+
+    /// Returns a seat plan of the same dimension with the given seats
+    pub fn with_seats(&self, seats: Vec<Seat>) -> Self {
+        Self {
+            seats,
+            ..*self
         }
+    }
 
-        new_plan
+    /// Returns the neighborhood of seats visible within the given number of steps
+    pub fn line_of_sight(&self, steps: u32) -> LineOfSight {
+        LineOfSight {
+            width: self.width,
+            height: self.height,
+            steps,
+        }
     }
 
     /// Return number of occupied adjacent seats
+    #[cfg(test)]
     pub fn adjacent(&self, x: i64, y: i64, steps: u32) -> u32 {
         let mut result = 0;
+        let index = x as usize + y as usize * self.width;
+
+        self.line_of_sight(steps).neighbors(&self.seats, index, &mut |neighbor| {
+            if self.seats[neighbor] == Seat::Occupied {
+                result += 1;
+            }
+        });
+
+        result
+    }
 
+    /// Returns the total number of occupied seats
+    pub fn total_occupied(&self) -> usize {
+        self.seats
+            .iter()
+            .filter(|&seat| *seat == Seat::Occupied)
+            .count()
+    }
+}
+
+/// The first seat in each of the eight directions within a number of steps
+#[derive(Clone, Debug)]
+struct LineOfSight {
+    width: usize,
+    height: usize,
+    steps: u32,
+}
+
+impl Neighborhood<Seat> for LineOfSight {
+    fn neighbors(&self, seats: &[Seat], index: usize, visit: &mut dyn FnMut(usize)) {
         // define all the directions
-        let dirs= vec![
+        let dirs = [
             (-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)
         ];
+        let x = (index % self.width) as i64;
+        let y = (index / self.width) as i64;
 
         for (i, j) in dirs.iter() {
             let mut sx = x;
             let mut sy = y;
 
-            for _ in 0..steps {
+            for _ in 0..self.steps {
                 sx += i;
                 sy += j;
 
-                // if adjacent seat is outside grid, advance to next
+                // if adjacent seat is outside grid, look into the next direction
                 if sx < 0 || sx >= self.width as i64 || sy < 0 || sy >= self.height as i64 {
-                    continue;
+                    break;
                 }
 
                 let index = (sx + sy * self.width as i64) as usize;
-                match self.seats[index] {
-                    Seat::Occupied => {
-                        result += 1;
-                        break;
-                    }
-                    Seat::Empty => break,
-                    Seat::Floor => (),
+                if seats[index] != Seat::Floor {
+                    visit(index);
+                    break;
                 }
             }
         }
+    }
+}
 
-        result
+/// Empty seats without occupied neighbors are taken, occupied seats with
+/// at least `occupied` occupied neighbors are left
+#[derive(Clone, Debug)]
+struct SeatingRule {
+    occupied: u32,
+}
+
+impl Rule<Seat> for SeatingRule {
+    fn next(&self, seat: &Seat, neighbors: &[&Seat]) -> Seat {
+        let adjacent = neighbors.iter().filter(|&&seat| *seat == Seat::Occupied).count() as u32;
+
+        match seat {
+            Seat::Empty => if adjacent == 0 { Seat::Occupied } else { Seat::Empty },
+            Seat::Occupied => if adjacent >= self.occupied { Seat::Empty } else { Seat::Occupied },
+            Seat::Floor => Seat::Floor,
+        }
     }
 
-    /// Returns the total number of occupied seats
-    pub fn total_occupied(&self) -> usize {
-        self.seats
-            .iter()
-            .filter(|&seat| *seat == Seat::Occupied)
-            .count()
+    fn is_static(&self, seat: &Seat) -> bool {
+        *seat == Seat::Floor
+    }
+}
+
+/// Iterator over all generations of a seat plan, starting with the initial plan
+/// and ending with the first plan that does not change anymore, or with the last
+/// plan before the seats repeat an earlier generation
+struct Generations {
+    plan: SeatPlan,
+    seating: Automaton<Seat, SeatingRule>,
+    remaining: usize,
+    started: bool,
+}
+
+impl Iterator for Generations {
+    type Item = SeatPlan;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        if self.started {
+            self.seating.step();
+        }
+        self.started = true;
+        self.remaining -= 1;
+        Some(self.plan.with_seats(self.seating.cells().to_vec()))
     }
 }
 
@@ -148,7 +245,7 @@ fn parse_seat_plan(input: &str) -> SeatPlan {
 
     let mut result: Vec<Seat> = vec![];
     for &row in seats.iter() {
-        for seat in row.chars().into_iter() {
+        for seat in row.chars() {
             result.push(seat.into());
         }
     }
@@ -160,45 +257,54 @@ fn parse_seat_plan(input: &str) -> SeatPlan {
     }
 }
 
-fn take_seats(mut plan: SeatPlan, occupied: u32, steps: u32) -> anyhow::Result<(u64, SeatPlan)> {
-    let mut iteration = 0u64;
-    loop {
-        let new_plan = plan.update(occupied, steps);
-        if new_plan == plan {
-            return Ok((iteration, new_plan));
-        }
-        plan = new_plan;
-        iteration += 1;
+/// The maximum number of generations to simulate
+const GENERATION_LIMIT: usize = 10_000;
 
-        // let's skip something after a number of iterations
-        if iteration >= 1_000 {
-            return Err(anyhow::anyhow!("Cycles run too many iterations"));
-        }
-    }
+/// Runs the seating rules until the seats stabilize or repeat, returns the outcome and last plan
+fn take_seats(plan: SeatPlan, occupied: u32, steps: u32) -> (Outcome, SeatPlan) {
+    let mut seating = plan.seating(occupied, steps);
+    let outcome = seating.run(GENERATION_LIMIT);
+    (outcome, plan.with_seats(seating.into_cells()))
 }
 
-fn take_seats_part_one(plan: SeatPlan) -> anyhow::Result<(u64, SeatPlan)> {
+fn take_seats_part_one(plan: SeatPlan) -> (Outcome, SeatPlan) {
     take_seats(plan, 4, 1)
 }
 
-fn take_seats_part_two(plan: SeatPlan) -> anyhow::Result<(u64, SeatPlan)> {
+fn take_seats_part_two(plan: SeatPlan) -> (Outcome, SeatPlan) {
     let dim = plan.max_dim();
     take_seats(plan, 5, dim)
 }
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     let plan = parse_seat_plan(include_str!("seats.txt"));
 
-    let (iteration, new_plan) = take_seats_part_one(plan.clone()).unwrap();
-    dbg!(iteration, new_plan.total_occupied());
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        let (occupied, steps) = if args.iter().any(|arg| arg == "--part-two") { (5, plan.max_dim()) } else { (4, 1) };
+        let generations = plan.generations(occupied, steps);
 
-    let (iteration, new_plan) = take_seats_part_two(plan).unwrap();
-    dbg!(iteration, new_plan.total_occupied());
+        match (args[0].as_str(), args.get(1)) {
+            ("--animate", _) => export::animate(generations, std::time::Duration::from_millis(100))?,
+            ("--ppm", Some(dir)) => export::write_ppm_frames(generations, dir, 4)?,
+            ("--gif", Some(path)) => export::write_gif(generations, path, 4, 10)?,
+            _ => return Err(anyhow::anyhow!("Usage: day-11 [--animate | --ppm <dir> | --gif <file>] [--part-two]")),
+        }
+        return Ok(());
+    }
+
+    let (outcome, new_plan) = take_seats_part_one(plan.clone());
+    dbg!(outcome, new_plan.total_occupied());
+
+    let (outcome, new_plan) = take_seats_part_two(plan);
+    dbg!(outcome, new_plan.total_occupied());
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{parse_seat_plan, take_seats};
+    use crate::{Outcome, parse_seat_plan, take_seats};
 
     const PLAN: &str = r#"
         L.LL.LL.LL
@@ -258,7 +364,7 @@ mod tests {
     #[test]
     fn test_run_take_seats() {
         let seat_plan = parse_seat_plan(PLAN);
-        let (iterations, final_plan) = take_seats(seat_plan, 4, 1).unwrap();
+        let (outcome, final_plan) = take_seats(seat_plan, 4, 1);
 
         let expected = parse_seat_plan(r#"
             #.#L.L#.##
@@ -273,7 +379,7 @@ mod tests {
             #.#L#L#.##
         "#);
 
-        assert_eq!(5, iterations);
+        assert_eq!(Outcome::Stabilized { generation: 5 }, outcome);
         assert_eq!(expected, final_plan);
         assert_eq!(37, final_plan.total_occupied());
     }
@@ -319,7 +425,7 @@ mod tests {
     fn test_take_seats_with_part_2_directions() {
         let plan = parse_seat_plan(PLAN);
         let width = plan.max_dim();
-        let (_, final_plan) = take_seats(plan, 5, width).unwrap();
+        let (_, final_plan) = take_seats(plan, 5, width);
 
         println!("{}", final_plan);
 
@@ -341,4 +447,33 @@ mod tests {
         assert_eq!(expected, final_plan);
         assert_eq!(26, final_plan.total_occupied());
     }
+
+    #[test]
+    fn test_generations() {
+        let plan = parse_seat_plan(PLAN);
+        let generations = plan.generations(4, 1).collect::<Vec<_>>();
+
+        assert_eq!(6, generations.len());
+        assert_eq!(plan, generations[0]);
+        assert_eq!(plan.update(4, 1), generations[1]);
+        assert_eq!(take_seats(plan, 4, 1).1, generations[5]);
+    }
+
+    #[test]
+    fn test_take_seats_oscillating() {
+        // two neighbors leave their seats as soon as the other one is occupied
+        let plan = parse_seat_plan("LL");
+        let (outcome, final_plan) = take_seats(plan, 1, 1);
+
+        assert_eq!(Outcome::Oscillating { start: 0, period: 2 }, outcome);
+        assert_eq!(parse_seat_plan("LL"), final_plan);
+    }
+
+    #[test]
+    fn test_generations_end_before_repetition() {
+        let plan = parse_seat_plan("LL");
+        let generations = plan.generations(1, 1).collect::<Vec<_>>();
+
+        assert_eq!(vec![plan, parse_seat_plan("##")], generations);
+    }
 }
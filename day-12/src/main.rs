@@ -1,4 +1,12 @@
-use std::ops::{Add, AddAssign, Mul, MulAssign};
+mod planner;
+mod plot;
+
+use std::{
+    convert::TryFrom,
+    fmt::{self, Display},
+    ops::{Add, AddAssign, Mul, MulAssign},
+    str::FromStr,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Point {
@@ -13,15 +21,6 @@ impl Point {
         Self { x, y }
     }
 
-    pub fn rotate(&mut self, degress: i32) {
-        *self = match degress {
-            90 | -270 => Point::new(self.y, -self.x),
-            180 | -180 => Point::new(-self.x, -self.y),
-            270 | -90 => Point::new(-self.y, self.x),
-            _ => *self,
-        };
-    }
-
     pub fn manhattan(&self) -> i32 {
         self.x.abs() + self.y.abs()
     }
@@ -72,94 +71,345 @@ impl From<(i32, i32)> for Point {
     }
 }
 
-const DIRS: [Point; 4] = [Point { x: 1, y: 0 }, Point { x: 0, y: -1 }, Point { x: -1, y:  0 }, Point { x: 0, y: 1 }];
+/// A vector that does not round to a point with 32 bit coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OutOfRange(Vector);
 
-/// Navigates the ship from start position 0, 0 until all instructions are processed
-/// Returns the final position of the ship
-fn navigate(instructions: &[&str]) -> Point {
-
-    let mut pos = Point{ x: 0, y: 0 };
-    let mut dir_index: usize = 0; // east
-
-    for &instruction in instructions.iter() {
-        let count = instruction[1..].parse::<i32>().unwrap();
-        let new_pos: Point = match &instruction[0..1] {
-            "N" => Point::new(0, count),
-            "E" => Point::new(count, 0),
-            "S" => Point::new(0, -count),
-            "W" => Point::new(-count, 0),
-            "L" => { dir_index = (dir_index as i32 - (count / 90)).rem_euclid(4) as usize; Point::ZERO },
-            "R" => { dir_index = (dir_index + (count / 90) as usize) % 4; Point::ZERO },
-            "F" => Point::new(count * DIRS[dir_index].x, count * DIRS[dir_index].y),
-            _ => panic!("Unexpected instruction found"),
+impl Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Position ({}, {}) does not fit into 32 bit coordinates", self.0.x, self.0.y)
+    }
+}
+
+impl std::error::Error for OutOfRange {}
+
+impl TryFrom<Vector> for Point {
+    type Error = OutOfRange;
+
+    /// Rounds the vector to the nearest point
+    fn try_from(vector: Vector) -> Result<Self, Self::Error> {
+        let round = |value: f64| match value.round() {
+            value if value >= i32::MIN as f64 && value <= i32::MAX as f64 => Ok(value as i32),
+            _ => Err(OutOfRange(vector)),
         };
+        Ok(Point::new(round(vector.x)?, round(vector.y)?))
+    }
+}
+
+/// A position or direction with arbitrary precision, used to rotate by any angle
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Vector {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vector {
+    const EAST: Vector = Vector { x: 1.0, y: 0.0 };
+
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    /// Rotates counter clockwise by the given degrees, multiples of 90 degrees are exact
+    pub fn rotated(self, degrees: i32) -> Self {
+        match degrees.rem_euclid(360) {
+            0 => self,
+            90 => Vector::new(-self.y, self.x),
+            180 => Vector::new(-self.x, -self.y),
+            270 => Vector::new(self.y, -self.x),
+            degrees => {
+                let (sin, cos) = (degrees as f64).to_radians().sin_cos();
+                Vector::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+            }
+        }
+    }
+}
+
+impl Add for Vector {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Vector::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Mul<Vector> for f64 {
+    type Output = Vector;
+
+    fn mul(self, rhs: Vector) -> Vector {
+        Vector::new(self * rhs.x, self * rhs.y)
+    }
+}
+
+impl From<Point> for Vector {
+    fn from(point: Point) -> Self {
+        Vector::new(point.x as f64, point.y as f64)
+    }
+}
+
+/// An error while parsing a navigation instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParseError {
+    Empty,
+    UnknownAction(char),
+    InvalidValue(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "Empty instruction"),
+            ParseError::UnknownAction(action) => write!(f, "Unknown action '{}'", action),
+            ParseError::InvalidValue(value) => write!(f, "Invalid value '{}'", value),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A single navigation instruction, turns are given in degrees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NavInstruction {
+    North(i32),
+    East(i32),
+    South(i32),
+    West(i32),
+    Left(i32),
+    Right(i32),
+    Forward(i32),
+}
 
-        pos += new_pos;
+impl FromStr for NavInstruction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let action = chars.next().ok_or(ParseError::Empty)?;
+        let value = chars
+            .as_str()
+            .parse::<i32>()
+            .map_err(|_| ParseError::InvalidValue(chars.as_str().to_string()))?;
+
+        Ok(match action {
+            'N' => NavInstruction::North(value),
+            'E' => NavInstruction::East(value),
+            'S' => NavInstruction::South(value),
+            'W' => NavInstruction::West(value),
+            'L' => NavInstruction::Left(value),
+            'R' => NavInstruction::Right(value),
+            'F' => NavInstruction::Forward(value),
+            _ => return Err(ParseError::UnknownAction(action)),
+        })
+    }
+}
+
+impl Display for NavInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NavInstruction::North(value) => write!(f, "N{}", value),
+            NavInstruction::East(value) => write!(f, "E{}", value),
+            NavInstruction::South(value) => write!(f, "S{}", value),
+            NavInstruction::West(value) => write!(f, "W{}", value),
+            NavInstruction::Left(value) => write!(f, "L{}", value),
+            NavInstruction::Right(value) => write!(f, "R{}", value),
+            NavInstruction::Forward(value) => write!(f, "F{}", value),
+        }
     }
+}
+
+/// Parses all instructions, fails on the first invalid one
+fn parse_instructions(instructions: &[&str]) -> Result<Vec<NavInstruction>, ParseError> {
+    instructions
+        .iter()
+        .map(|instruction| instruction.parse())
+        .collect()
+}
 
-    pos
+/// What the instructions `N`, `E`, `S`, `W`, `L` and `R` move or turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Ship,
+    Waypoint,
 }
 
-fn navigate_waypoint(instructions: &[&str]) -> Point {
-    let mut pos = Point::new(0, 0);
-    let mut waypoint = Point::new(10, 1);
+/// The state of the ship after an instruction
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct State {
+    pub ship: Vector,
+    /// The heading of the ship in degrees, counter clockwise, 0 is east
+    pub heading: i32,
+    /// The waypoint relative to the ship
+    pub waypoint: Vector,
+}
 
-    for &instruction in instructions.iter() {
-        let count = instruction[1..].parse::<i32>().unwrap();
-        match &instruction[0..1] {
-            "N" => { waypoint += Point::new(0, count); },
-            "E" => { waypoint += Point::new(count, 0); },
-            "S" => { waypoint += Point::new(0, -count); },
-            "W" => { waypoint += Point::new(-count, 0); },
-            "F" => { pos += count * waypoint },
-            "L" => { waypoint.rotate(-count); },
-            "R" => { waypoint.rotate(count); },
-            _ => panic!("unsupported instruction found"),
+impl State {
+    pub fn start() -> Self {
+        Self {
+            ship: Vector::new(0.0, 0.0),
+            heading: 0,
+            waypoint: Vector::new(10.0, 1.0),
+        }
+    }
+
+    /// Returns the state after the instruction was executed
+    pub fn apply(self, instruction: NavInstruction, mode: Mode) -> Self {
+        let shift = |state: Self, offset: Vector| match mode {
+            Mode::Ship => Self { ship: state.ship + offset, ..state },
+            Mode::Waypoint => Self { waypoint: state.waypoint + offset, ..state },
         };
+        let turn = |state: Self, degrees: i32| match mode {
+            Mode::Ship => Self { heading: (state.heading + degrees).rem_euclid(360), ..state },
+            Mode::Waypoint => Self { waypoint: state.waypoint.rotated(degrees), ..state },
+        };
+
+        match instruction {
+            NavInstruction::North(value) => shift(self, Vector::new(0.0, value as f64)),
+            NavInstruction::East(value) => shift(self, Vector::new(value as f64, 0.0)),
+            NavInstruction::South(value) => shift(self, Vector::new(0.0, -value as f64)),
+            NavInstruction::West(value) => shift(self, Vector::new(-value as f64, 0.0)),
+            NavInstruction::Left(degrees) => turn(self, degrees),
+            NavInstruction::Right(degrees) => turn(self, -degrees),
+            NavInstruction::Forward(value) => {
+                let direction = match mode {
+                    Mode::Ship => Vector::EAST.rotated(self.heading),
+                    Mode::Waypoint => self.waypoint,
+                };
+                Self { ship: self.ship + value as f64 * direction, ..self }
+            }
+        }
     }
+}
 
-    pos
+/// Runs all instructions, returns the start state followed by the state after every instruction
+fn simulate(instructions: &[NavInstruction], mode: Mode) -> Vec<State> {
+    let mut states = vec![State::start()];
+    for &instruction in instructions {
+        let state = states[states.len() - 1].apply(instruction, mode);
+        states.push(state);
+    }
+    states
+}
+
+/// Navigates the ship from start position 0, 0 until all instructions are processed
+/// Returns the final position of the ship, fails if it does not fit into 32 bit coordinates
+fn navigate(instructions: &[&str]) -> anyhow::Result<Point> {
+    let instructions = parse_instructions(instructions)?;
+    let states = simulate(&instructions, Mode::Ship);
+    Ok(states.last().map_or(Ok(Point::ZERO), |state| Point::try_from(state.ship))?)
 }
 
-fn main() {
+fn navigate_waypoint(instructions: &[&str]) -> anyhow::Result<Point> {
+    let instructions = parse_instructions(instructions)?;
+    let states = simulate(&instructions, Mode::Waypoint);
+    Ok(states.last().map_or(Ok(Point::ZERO), |state| Point::try_from(state.ship))?)
+}
+
+fn main() -> anyhow::Result<()> {
     let instructions = include_str!("ferry.txt")
         .lines()
         .map(str::trim)
         .filter(|&line| !line.is_empty())
         .collect::<Vec<_>>();
 
-    let point = navigate(&instructions);
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        [flag @ ("--svg" | "--svg-waypoint"), path] => {
+            let mode = if *flag == "--svg-waypoint" { Mode::Waypoint } else { Mode::Ship };
+            let states = simulate(&parse_instructions(&instructions)?, mode);
+            std::fs::write(path, plot::to_svg(&states, mode))?;
+            return Ok(());
+        }
+        [flag @ ("--plan" | "--plan-waypoint"), x, y, heading @ ..] => {
+            let mode = if *flag == "--plan-waypoint" { Mode::Waypoint } else { Mode::Ship };
+            let target = Point::new(x.parse()?, y.parse()?);
+            let heading = heading.first().map_or(Ok(0), |heading| heading.parse())?;
+            match planner::plan_route(target, heading, mode) {
+                Some(route) => route.iter().for_each(|instruction| println!("{}", instruction)),
+                None => println!("Target cannot be reached"),
+            }
+            return Ok(());
+        }
+        _ => (),
+    }
+
+    let point = navigate(&instructions)?;
     dbg!(point.manhattan());
 
-    let point = navigate_waypoint(&instructions);
+    let point = navigate_waypoint(&instructions)?;
     dbg!(point.manhattan());
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Point, navigate, navigate_waypoint};
+    use std::convert::TryFrom;
+
+    use crate::{Mode, NavInstruction, ParseError, Point, Vector, navigate, navigate_waypoint, parse_instructions, simulate};
 
     #[test]
     fn test_navigate_ship() {
         let instructions = vec!["F10", "N3", "F7", "R90", "F11"];
-        assert_eq!(Point::new(17, -8), navigate(&instructions));
+        assert_eq!(Point::new(17, -8), navigate(&instructions).unwrap());
     }
 
     #[test]
     fn test_navigation_with_turns() {
         let instructions = vec!["R90", "L90", "L90", "L90", "L90", "R270", "F10"];
-        assert_eq!(Point::new(10, 0), navigate(&instructions));
+        assert_eq!(Point::new(10, 0), navigate(&instructions).unwrap());
     }
 
     #[test]
     fn test_navigate_with_waypoint() {
         let instructions = vec!["F10", "N3", "F7", "R90", "F11"];
-        assert_eq!(Point::new(214, -72), navigate_waypoint(&instructions));
+        assert_eq!(Point::new(214, -72), navigate_waypoint(&instructions).unwrap());
     }
 
     #[test]
     fn test_navigate_with_longer_waypoint() {
         let instructions = vec!["F5", "R90", "L270", "N2", "W5", "F3", "L90", "F2"];
-        assert_eq!(Point::new(3, -22), navigate_waypoint(&instructions));
+        assert_eq!(Point::new(3, -22), navigate_waypoint(&instructions).unwrap());
+    }
+
+    #[test]
+    fn test_parse_instructions() {
+        assert_eq!(Ok(NavInstruction::Forward(10)), "F10".parse());
+        assert_eq!(Ok(NavInstruction::Left(45)), "L45".parse());
+        assert_eq!(Err(ParseError::UnknownAction('X')), "X3".parse::<NavInstruction>());
+        assert_eq!(Err(ParseError::InvalidValue("1a".to_string())), "N1a".parse::<NavInstruction>());
+        assert_eq!(Err(ParseError::Empty), "".parse::<NavInstruction>());
+        assert!(navigate(&["F10", "Q1"]).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_simulate_records_trajectory() {
+        let instructions = parse_instructions(&["F10", "N3", "F7", "R90", "F11"]).unwrap();
+        let states = simulate(&instructions, Mode::Ship);
+
+        let positions = states.iter().map(|state| Point::try_from(state.ship).unwrap()).collect::<Vec<_>>();
+        let headings = states.iter().map(|state| state.heading).collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![Point::new(0, 0), Point::new(10, 0), Point::new(10, 3), Point::new(17, 3), Point::new(17, 3), Point::new(17, -8)],
+            positions,
+        );
+        assert_eq!(vec![0, 0, 0, 0, 270, 270], headings);
+    }
+
+    #[test]
+    fn test_navigate_arbitrary_angles() {
+        assert_eq!(Point::new(7, 7), navigate(&["L45", "F10"]).unwrap());
+        assert_eq!(Point::new(0, -10), navigate(&["R45", "R45", "F10"]).unwrap());
+        assert_eq!(Point::new(-10, 0), navigate(&["L540", "F10"]).unwrap());
+        assert_eq!(Point::new(0, 10), navigate_waypoint(&["S1", "L45", "L45", "F1"]).unwrap());
+
+        // positions beyond 32 bit coordinates are errors instead of being clamped
+        assert!(navigate(&["F2000000000", "F2000000000"]).is_err());
+        assert!(navigate_waypoint(&["F2000000000"]).is_err());
+        assert_eq!(Point::new(i32::MAX, 0), navigate(&["F2147483647"]).unwrap());
+
+        // right angles stay exact
+        let rotated = Vector::new(3.0, 4.0).rotated(-270);
+        assert_eq!(Vector::new(-4.0, 3.0), rotated);
+    }
+}
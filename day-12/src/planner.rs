@@ -0,0 +1,151 @@
+use std::convert::TryFrom;
+
+use crate::{Mode, NavInstruction, Point, State, Vector};
+
+/// Returns the turn with the smallest angle from heading 0 to the given heading
+fn turn(heading: i32) -> Option<NavInstruction> {
+    match heading.rem_euclid(360) {
+        0 => None,
+        degrees if degrees <= 180 => Some(NavInstruction::Left(degrees)),
+        degrees => Some(NavInstruction::Right(360 - degrees)),
+    }
+}
+
+/// Returns the instruction to move `value` units along the x axis
+fn horizontal(value: i32) -> Option<NavInstruction> {
+    match value {
+        0 => None,
+        value if value > 0 => Some(NavInstruction::East(value)),
+        value => Some(NavInstruction::West(-value)),
+    }
+}
+
+/// Returns the instruction to move `value` units along the y axis
+fn vertical(value: i32) -> Option<NavInstruction> {
+    match value {
+        0 => None,
+        value if value > 0 => Some(NavInstruction::North(value)),
+        value => Some(NavInstruction::South(-value)),
+    }
+}
+
+/// Returns the positive factor `k` with `k * direction == target`, if there is one
+fn multiple(target: Point, direction: Point) -> Option<i32> {
+    let factor = match (direction.x, direction.y) {
+        (0, 0) => return None,
+        (0, y) => target.y / y,
+        (x, _) => target.x / x,
+    };
+
+    if factor > 0 && factor * direction == target {
+        Some(factor)
+    } else {
+        None
+    }
+}
+
+/// Plans the route of the ship that moves and turns on its own.
+///
+/// Every axis and the heading need at most one instruction, the ship faces east at the start.
+fn plan_ship(target: Point, heading: i32) -> Vec<NavInstruction> {
+    let x = match target.x {
+        x if x > 0 => Some(NavInstruction::Forward(x)),
+        x => horizontal(x),
+    };
+
+    vec![x, vertical(target.y), turn(heading)]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Plans the route of the ship that only moves forward towards the waypoint.
+///
+/// The ship reaches the target with a single `F` if the target is a multiple of the waypoint,
+/// with two instructions if a single change of the waypoint makes it so and with three
+/// instructions otherwise, by moving the waypoint onto the target.
+fn plan_waypoint(target: Point) -> Option<Vec<NavInstruction>> {
+    let start = Point::try_from(State::start().waypoint).ok()?;
+    if target == Point::ZERO {
+        return Some(vec![]);
+    }
+    if let Some(factor) = multiple(target, start) {
+        return Some(vec![NavInstruction::Forward(factor)]);
+    }
+
+    // all waypoints that are a single instruction away and lie on the line to the target
+    let mut candidates = vec![];
+    if target.y > 0 && target.x % target.y == 0 {
+        candidates.push((horizontal(target.x / target.y - start.x), Point::new(target.x / target.y, 1)));
+    }
+    if target.x > 0 && target.x % start.x == 0 {
+        let factor = target.x / start.x;
+        if target.y % factor == 0 {
+            candidates.push((vertical(target.y / factor - start.y), Point::new(start.x, target.y / factor)));
+        }
+    }
+    for &degrees in [90, 180, 270].iter() {
+        candidates.push((turn(degrees), Point::try_from(Vector::from(start).rotated(degrees)).ok()?));
+    }
+
+    for (instruction, waypoint) in candidates {
+        if let (Some(instruction), Some(factor)) = (instruction, multiple(target, waypoint)) {
+            return Some(vec![instruction, NavInstruction::Forward(factor)]);
+        }
+    }
+
+    let route = vec![horizontal(target.x - start.x), vertical(target.y - start.y), Some(NavInstruction::Forward(1))];
+    Some(route.into_iter().flatten().collect())
+}
+
+/// Plans the shortest sequence of instructions that brings the ship from the origin to the target
+/// position with the given heading, in degrees counter clockwise from east.
+///
+/// Returns `None` if the target cannot be reached, in waypoint mode the ship never turns.
+pub fn plan_route(target: Point, heading: i32, mode: Mode) -> Option<Vec<NavInstruction>> {
+    match mode {
+        Mode::Ship => Some(plan_ship(target, heading)),
+        Mode::Waypoint if heading.rem_euclid(360) == 0 => plan_waypoint(target),
+        Mode::Waypoint => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::plan_route;
+    use crate::{Mode, NavInstruction::*, Point, simulate};
+
+    fn destination(instructions: &[crate::NavInstruction], mode: Mode) -> (Point, i32) {
+        let state = *simulate(instructions, mode).last().unwrap();
+        (Point::try_from(state.ship).unwrap(), state.heading)
+    }
+
+    #[test]
+    fn test_plan_ship_route() {
+        assert_eq!(Some(vec![]), plan_route(Point::ZERO, 360, Mode::Ship));
+        assert_eq!(Some(vec![Forward(17), South(8), Right(90)]), plan_route(Point::new(17, -8), 270, Mode::Ship));
+        assert_eq!(Some(vec![West(3), Left(180)]), plan_route(Point::new(-3, 0), 180, Mode::Ship));
+
+        let plan = plan_route(Point::new(-25, 12), 45, Mode::Ship).unwrap();
+        assert_eq!(3, plan.len());
+        assert_eq!((Point::new(-25, 12), 45), destination(&plan, Mode::Ship));
+    }
+
+    #[test]
+    fn test_plan_waypoint_route() {
+        assert_eq!(Some(vec![Forward(3)]), plan_route(Point::new(30, 3), 0, Mode::Waypoint));
+        assert_eq!(Some(vec![East(2), Forward(4)]), plan_route(Point::new(48, 4), 0, Mode::Waypoint));
+        assert_eq!(Some(vec![North(2), Forward(2)]), plan_route(Point::new(20, 6), 0, Mode::Waypoint));
+        assert_eq!(Some(vec![Left(90), Forward(5)]), plan_route(Point::new(-5, 50), 0, Mode::Waypoint));
+        assert_eq!(Some(vec![West(11), Forward(7)]), plan_route(Point::new(-7, 7), 0, Mode::Waypoint));
+        assert_eq!(Some(vec![West(3), South(4), Forward(1)]), plan_route(Point::new(7, -3), 0, Mode::Waypoint));
+        assert_eq!(None, plan_route(Point::new(1, 1), 90, Mode::Waypoint));
+
+        for &(x, y) in [(0, 0), (-3, -4), (25, 1), (10, -7), (-20, -2), (11, 11)].iter() {
+            let plan = plan_route(Point::new(x, y), 0, Mode::Waypoint).unwrap();
+            assert_eq!((Point::new(x, y), 0), destination(&plan, Mode::Waypoint), "{:?}", plan);
+        }
+    }
+}
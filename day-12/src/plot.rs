@@ -0,0 +1,77 @@
+use std::fmt::Write;
+
+use crate::{Mode, State, Vector};
+
+/// The margin around the plotted paths
+const MARGIN: f64 = 20.0;
+
+/// Flips the y axis, it points down in SVG
+fn flip(y: f64) -> f64 {
+    0.0 - y
+}
+
+/// Formats the points as SVG polyline
+fn polyline(points: &[Vector], color: &str) -> String {
+    let points = points
+        .iter()
+        .map(|point| format!("{:.1},{:.1}", point.x, flip(point.y)))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2" />"#, points, color)
+}
+
+/// Plots the path of the ship and in waypoint mode the path of the waypoint as SVG image
+pub fn to_svg(states: &[State], mode: Mode) -> String {
+    let ship = states.iter().map(|state| state.ship).collect::<Vec<_>>();
+    let waypoint = match mode {
+        Mode::Waypoint => states.iter().map(|state| state.ship + state.waypoint).collect::<Vec<_>>(),
+        Mode::Ship => Vec::new(),
+    };
+
+    let points = ship.iter().chain(waypoint.iter());
+    let (min_x, max_x, min_y, max_y) = points.fold((0.0_f64, 0.0_f64, 0.0_f64, 0.0_f64), |(min_x, max_x, min_y, max_y), point| {
+        (min_x.min(point.x), max_x.max(point.x), min_y.min(flip(point.y)), max_y.max(flip(point.y)))
+    });
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.1} {:.1} {:.1} {:.1}">"#,
+        min_x - MARGIN,
+        min_y - MARGIN,
+        max_x - min_x + 2.0 * MARGIN,
+        max_y - min_y + 2.0 * MARGIN,
+    ).unwrap();
+
+    if !waypoint.is_empty() {
+        writeln!(svg, "  {}", polyline(&waypoint, "orange")).unwrap();
+    }
+    writeln!(svg, "  {}", polyline(&ship, "steelblue")).unwrap();
+    writeln!(svg, r#"  <circle cx="0" cy="0" r="4" fill="green" />"#).unwrap();
+    if let Some(last) = ship.last() {
+        writeln!(svg, r#"  <circle cx="{:.1}" cy="{:.1}" r="4" fill="red" />"#, last.x, flip(last.y)).unwrap();
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_svg;
+    use crate::{Mode, parse_instructions, simulate};
+
+    #[test]
+    fn test_svg_plot() {
+        let instructions = parse_instructions(&["F10", "N3", "F7", "R90", "F11"]).unwrap();
+
+        let svg = to_svg(&simulate(&instructions, Mode::Ship), Mode::Ship);
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-20.0 -23.0 57.0 51.0">"#));
+        assert!(svg.contains(r#"points="0.0,0.0 10.0,0.0 10.0,-3.0 17.0,-3.0 17.0,-3.0 17.0,8.0""#));
+        assert_eq!(1, svg.matches("<polyline").count());
+
+        let svg = to_svg(&simulate(&instructions, Mode::Waypoint), Mode::Waypoint);
+        assert_eq!(2, svg.matches("<polyline").count());
+    }
+}
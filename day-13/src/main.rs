@@ -18,39 +18,217 @@ fn parse_input(content: &str) -> anyhow::Result<(u64, String)> {
 /// Finds the earliest bus that departs to the airport including number of minutes
 /// The tuple consists of `(minutes, bus_id)`.
 fn find_earliest_bus(timestamp: u64, bus_ids: &str) -> Option<(u64, u64)> {
-    bus_ids
-        .split(',')
-        .filter(|&v| v != "x")
-        .map(|v| v.parse::<u64>().unwrap())
-        .map(|bus_id| (bus_id - timestamp % bus_id, bus_id))
-        .min_by_key(|v| v.0)
+    Timetable::parse(bus_ids).ok()?.earliest_departure(timestamp)
 }
 
-/// Finds the earliest timestamp where the given list of bus ids follow the pattern that
-/// every bus departs 1 minute later than the previous one. All buses need to conform to this pattern
-/// 'x' entries are "wild cards" that bridge a gap. All 'x' gaps are marked as 1 to simulate that
-/// the bus departs every minute.
-fn find_earliest_timestamp(bus_ids: &str) -> Option<u64> {
-    let bus_ids = bus_ids
-        .split(',')
-        .map(|v| v.parse::<u64>().unwrap_or(1))
-        .collect::<Vec<_>>();
+/// The reason a system of congruences has no result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CrtError {
+    /// The congruence at the given index contradicts the previous ones
+    Inconsistent(usize),
+    /// The combined modulus does not fit into 128 bits
+    Overflow,
+}
+
+impl std::fmt::Display for CrtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CrtError::Inconsistent(index) => write!(f, "Congruence {} contradicts the previous ones", index),
+            CrtError::Overflow => write!(f, "Combined modulus overflows"),
+        }
+    }
+}
+
+impl std::error::Error for CrtError {}
+
+/// Returns `(g, x, y)` with `a * x + b * y = g = gcd(a, b)`
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
 
-    // optimize here, all numbers are primes, does it matter?
-    let bus_id = *bus_ids.first()?;
-    let result = bus_ids
+/// Solves the system of congruences `x = remainder (mod modulus)` with the Chinese Remainder Theorem.
+///
+/// The moduli do not need to be coprime. Returns the smallest non negative solution
+/// together with the least common multiple of all moduli, all solutions differ by a multiple of it.
+fn solve_crt(congruences: &[(i128, i128)]) -> Result<(i128, i128), CrtError> {
+    congruences
         .iter()
         .enumerate()
-        .fold((bus_id, 1), |(result, step), (index, &bus_id) | {
-            let new_result = (result..)
-                .step_by(step)
-                .find(|timestamp| (timestamp + index as u64) % bus_id == 0)
-                .expect("Nothing found");
+        .try_fold((0, 1), |(result, modulus), (index, &(remainder, other))| {
+            let remainder = remainder.rem_euclid(other);
+            let (g, inverse, _) = extended_gcd(modulus, other);
+
+            let difference = remainder - result;
+            if difference % g != 0 {
+                return Err(CrtError::Inconsistent(index));
+            }
 
-            (new_result, step * bus_id as usize)
-        });
+            let step = other / g;
+            let lcm = modulus.checked_mul(step).ok_or(CrtError::Overflow)?;
+            let factor = ((difference / g) % step * (inverse % step)).rem_euclid(step);
+            let result = factor
+                .checked_mul(modulus)
+                .and_then(|offset| offset.checked_add(result))
+                .ok_or(CrtError::Overflow)?;
 
-    Some(result.0)
+            Ok((result.rem_euclid(lcm), lcm))
+        })
+}
+
+/// A time span in which several buses depart
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Window {
+    /// The first departure of the window
+    start: u64,
+    /// All departures of the window as `(timestamp, bus_id)`, ordered by time
+    departures: Vec<(u64, u64)>,
+}
+
+/// The bus lines in order of the schedule, `x` entries are `None`.
+///
+/// Every bus departs at every multiple of its id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Timetable {
+    lines: Vec<Option<u64>>,
+}
+
+impl Timetable {
+    pub fn new(lines: Vec<Option<u64>>) -> Self {
+        Self { lines }
+    }
+
+    /// Parses the comma separated bus ids, `x` marks a line that is out of service
+    pub fn parse(bus_ids: &str) -> anyhow::Result<Self> {
+        let lines = bus_ids
+            .trim()
+            .split(',')
+            .map(|v| match v.trim() {
+                "x" => Ok(None),
+                v => match v.parse::<u64>() {
+                    Ok(0) => Err(anyhow!("Bus id must not be 0")),
+                    Ok(bus_id) => Ok(Some(bus_id)),
+                    Err(err) => Err(anyhow!("Failed to parse bus id '{}': {}", v, err)),
+                },
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self::new(lines))
+    }
+
+    /// Returns the ids of all buses in service, without duplicates
+    pub fn bus_ids(&self) -> Vec<u64> {
+        let mut bus_ids = self.lines.iter().flatten().copied().collect::<Vec<_>>();
+        bus_ids.sort_unstable();
+        bus_ids.dedup();
+        bus_ids
+    }
+
+    /// Returns the next `count` departures of the bus at or after the timestamp
+    pub fn next_departures(&self, bus_id: u64, timestamp: u64, count: usize) -> Vec<u64> {
+        if !self.lines.contains(&Some(bus_id)) {
+            return vec![];
+        }
+
+        let first = timestamp.div_ceil(bus_id) * bus_id;
+        (0..count as u64).map(|n| first + n * bus_id).collect()
+    }
+
+    /// Finds the bus that departs first at or after the timestamp.
+    /// The tuple consists of `(minutes, bus_id)`.
+    pub fn earliest_departure(&self, timestamp: u64) -> Option<(u64, u64)> {
+        self.bus_ids()
+            .into_iter()
+            .map(|bus_id| ((bus_id - timestamp % bus_id) % bus_id, bus_id))
+            .min_by_key(|v| v.0)
+    }
+
+    /// Finds the earliest timestamp after 0 where every given bus departs `offset` minutes later.
+    ///
+    /// The pairs are `(bus_id, offset)`, all buses need to be in service.
+    pub fn earliest_with_offsets(&self, buses: &[(u64, u64)]) -> anyhow::Result<u128> {
+        let congruences = buses
+            .iter()
+            .map(|&(bus_id, offset)| {
+                if self.lines.contains(&Some(bus_id)) {
+                    Ok((-(offset as i128), bus_id as i128))
+                } else {
+                    Err(anyhow!("Bus {} is not in service", bus_id))
+                }
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let (timestamp, modulus) = solve_crt(&congruences)?;
+        Ok(if timestamp == 0 { modulus } else { timestamp } as u128)
+    }
+
+    /// Finds the earliest timestamp where every bus departs as many minutes later as its
+    /// position in the timetable.
+    pub fn earliest_in_sequence(&self) -> anyhow::Result<u128> {
+        let buses = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, bus_id)| bus_id.map(|bus_id| (bus_id, index as u64)))
+            .collect::<Vec<_>>();
+
+        self.earliest_with_offsets(&buses)
+    }
+
+    /// Returns all windows of `minutes` length starting in `from..to` in which at least
+    /// `min_buses` different buses depart. Every window starts with a departure.
+    pub fn busy_windows(&self, from: u64, to: u64, min_buses: usize, minutes: u64) -> Vec<Window> {
+        let end = to.saturating_add(minutes);
+        let mut departures = self
+            .bus_ids()
+            .into_iter()
+            .flat_map(|bus_id| {
+                let first = from.div_ceil(bus_id) * bus_id;
+                (first..end).step_by(bus_id as usize).map(move |timestamp| (timestamp, bus_id))
+            })
+            .collect::<Vec<_>>();
+        departures.sort_unstable();
+
+        let mut windows = Vec::new();
+        let mut last = 0;
+        for (first, &(start, _)) in departures.iter().enumerate() {
+            if start >= to {
+                break;
+            }
+            if first > 0 && departures[first - 1].0 == start {
+                continue;
+            }
+
+            last = last.max(first);
+            while last < departures.len() && departures[last].0 < start + minutes {
+                last += 1;
+            }
+
+            let window = &departures[first..last];
+            let mut buses = window.iter().map(|&(_, bus_id)| bus_id).collect::<Vec<_>>();
+            buses.sort_unstable();
+            buses.dedup();
+
+            if buses.len() >= min_buses {
+                windows.push(Window { start, departures: window.to_vec() });
+            }
+        }
+
+        windows
+    }
+}
+
+/// Finds the earliest timestamp where the given list of bus ids follow the pattern that
+/// every bus departs 1 minute later than the previous one. All buses need to conform to this pattern,
+/// 'x' entries are "wild cards" that bridge a gap.
+///
+/// Returns `None` if there is no such timestamp, the timestamp 0 is not taken into account.
+fn find_earliest_timestamp(bus_ids: &str) -> Option<u128> {
+    Timetable::parse(bus_ids).ok()?.earliest_in_sequence().ok()
 }
 
 fn main() -> anyhow::Result<()> {
@@ -62,21 +240,19 @@ fn main() -> anyhow::Result<()> {
     let timestamp = find_earliest_timestamp(&bus_ids);
     dbg!(timestamp);
 
+    let timetable = Timetable::parse(&bus_ids)?;
+    if let Some(&bus_id) = timetable.bus_ids().first() {
+        dbg!(timetable.next_departures(bus_id, 0, 5));
+    }
+    let start = timestamp.unwrap_or_default() as u64;
+    dbg!(timetable.busy_windows(start, start + 100, 3, 5).first());
+
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{find_earliest_bus, find_earliest_timestamp, is_prime, parse_input};
-
-    #[test]
-    fn test_is_prime() {
-        assert_eq!(true, is_prime(2));
-        assert_eq!(true, is_prime(3));
-        assert_eq!(true, is_prime(5));
-        assert_eq!(true, is_prime(17));
-        assert_eq!(true, is_prime(41));
-    }
+    use crate::{CrtError, Timetable, Window, find_earliest_bus, find_earliest_timestamp, parse_input, solve_crt};
 
     #[test]
     fn test_parse_input() {
@@ -114,4 +290,61 @@ mod tests {
         assert_eq!(Some(1261476), find_earliest_timestamp("67,7,x,59,61"));
         assert_eq!(Some(1202161486), find_earliest_timestamp("1789,37,47,1889"));
     }
+
+    #[test]
+    fn test_solve_crt_with_non_coprime_moduli() {
+        assert_eq!(Ok((10, 12)), solve_crt(&[(2, 4), (4, 6)]));
+        assert_eq!(Ok((23, 105)), solve_crt(&[(2, 3), (3, 5), (2, 7)]));
+        assert_eq!(Ok((0, 1)), solve_crt(&[]));
+        assert_eq!(Err(CrtError::Inconsistent(1)), solve_crt(&[(1, 4), (2, 6)]));
+    }
+
+    #[test]
+    fn test_solve_crt_overflow() {
+        let primes = [1_000_000_007, 998_244_353, 1_000_000_009, 999_999_937, 2_147_483_647];
+        let congruences = primes.iter().map(|&p| (1, p)).collect::<Vec<_>>();
+        assert_eq!(Err(CrtError::Overflow), solve_crt(&congruences));
+    }
+
+    #[test]
+    fn test_unsatisfiable_timestamp() {
+        assert_eq!(None, find_earliest_timestamp("4,6"));
+        assert_eq!(Some(2), find_earliest_timestamp("2,x,2"));
+    }
+
+    #[test]
+    fn test_parse_timetable() {
+        let timetable = Timetable::parse("7,13,x,x,59").unwrap();
+        assert_eq!(Timetable::new(vec![Some(7), Some(13), None, None, Some(59)]), timetable);
+        assert!(Timetable::parse("7,y,13").is_err());
+        assert!(Timetable::parse("7,0").is_err());
+    }
+
+    #[test]
+    fn test_next_departures() {
+        let timetable = Timetable::parse("7,13,x,x,59,x,31,19").unwrap();
+        assert_eq!(vec![944, 1003, 1062], timetable.next_departures(59, 939, 3));
+        assert_eq!(vec![945], timetable.next_departures(7, 945, 1));
+        assert!(timetable.next_departures(11, 939, 3).is_empty());
+        assert_eq!(Some((0, 7)), timetable.earliest_departure(945));
+    }
+
+    #[test]
+    fn test_earliest_with_offsets() {
+        let timetable = Timetable::parse("7,13,x,x,59,x,31,19").unwrap();
+        assert_eq!(1068781, timetable.earliest_in_sequence().unwrap());
+        assert_eq!(77, timetable.earliest_with_offsets(&[(7, 0), (13, 1)]).unwrap());
+        assert_eq!(1068781, timetable.earliest_with_offsets(&[(19, 7), (59, 4), (31, 6), (7, 0), (13, 1)]).unwrap());
+        assert!(timetable.earliest_with_offsets(&[(11, 0)]).is_err());
+    }
+
+    #[test]
+    fn test_busy_windows() {
+        let timetable = Timetable::parse("3,x,5,7").unwrap();
+        let windows = timetable.busy_windows(0, 30, 3, 3);
+
+        assert_eq!(vec![0, 5, 14, 20, 28], windows.iter().map(|w| w.start).collect::<Vec<_>>());
+        assert_eq!(Window { start: 5, departures: vec![(5, 5), (6, 3), (7, 7)] }, windows[1]);
+        assert!(timetable.busy_windows(0, 30, 4, 3).is_empty());
+    }
 }
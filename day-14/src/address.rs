@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+/// A set of addresses, all combinations of the `floating` bits on top of the `fixed` bits.
+///
+/// The fixed bits are always 0 where a bit is floating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AddressSet {
+    pub fixed: u128,
+    pub floating: u128,
+}
+
+impl AddressSet {
+    pub fn new(fixed: u128, floating: u128) -> Self {
+        Self { fixed: fixed & !floating, floating }
+    }
+
+    /// Returns the number of addresses in the set
+    pub fn len(&self) -> Option<u128> {
+        1u128.checked_shl(self.floating.count_ones())
+    }
+
+    /// Returns true if the address is part of the set
+    #[cfg(test)]
+    pub fn contains(&self, address: u128) -> bool {
+        address & !self.floating == self.fixed
+    }
+
+    /// Returns true if both sets share at least one address
+    pub fn intersects(&self, other: &AddressSet) -> bool {
+        (self.fixed ^ other.fixed) & !self.floating & !other.floating == 0
+    }
+
+    /// Returns the addresses of this set that are not in the other set, as disjoint sets.
+    ///
+    /// Every floating bit that is fixed in the other set splits off the half that differs from it.
+    pub fn subtract(&self, other: &AddressSet) -> Vec<AddressSet> {
+        if !self.intersects(other) {
+            return vec![*self];
+        }
+
+        let mut rest = *self;
+        let mut pieces = Vec::new();
+        let mut bits = self.floating & !other.floating;
+        while bits != 0 {
+            let bit = bits & bits.wrapping_neg();
+            bits &= !bit;
+
+            let floating = rest.floating & !bit;
+            pieces.push(AddressSet::new(rest.fixed | (!other.fixed & bit), floating));
+            rest = AddressSet::new(rest.fixed | (other.fixed & bit), floating);
+        }
+
+        pieces
+    }
+}
+
+/// The memory of a docking computer, the backend is chosen by the decoder
+pub trait Memory {
+    /// Writes the value to all addresses of the set
+    fn write(&mut self, addresses: AddressSet, value: u128);
+
+    /// Returns the sum of the values of all addresses, `None` if it does not fit into 128 bits
+    fn sum(&self) -> Option<u128>;
+
+    /// Returns all address sets with a value other than 0, ordered by their fixed bits
+    fn non_zero(&self) -> Vec<(AddressSet, u128)>;
+}
+
+/// A memory of single addresses, a write to a set with floating bits writes every address
+#[derive(Debug, Default)]
+pub struct DirectMemory {
+    values: HashMap<u128, u128>,
+}
+
+impl Memory for DirectMemory {
+    fn write(&mut self, addresses: AddressSet, value: u128) {
+        // visit all subsets of the floating bits
+        let mut bits = 0u128;
+        loop {
+            self.values.insert(addresses.fixed | bits, value);
+            bits = bits.wrapping_sub(addresses.floating) & addresses.floating;
+            if bits == 0 {
+                break;
+            }
+        }
+    }
+
+    fn sum(&self) -> Option<u128> {
+        self.values.values().try_fold(0u128, |sum, &value| sum.checked_add(value))
+    }
+
+    fn non_zero(&self) -> Vec<(AddressSet, u128)> {
+        let mut entries = self
+            .values
+            .iter()
+            .filter(|&(_, &value)| value != 0)
+            .map(|(&address, &value)| (AddressSet::new(address, 0), value))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|&(set, _)| set.fixed);
+        entries
+    }
+}
+
+/// A list of disjoint address sets with their value, floating addresses are never enumerated
+#[derive(Debug, Default)]
+pub struct SparseMemory {
+    entries: Vec<(AddressSet, u128)>,
+}
+
+impl Memory for SparseMemory {
+    /// Writes the value to all addresses of the set, earlier writes are cut out of the overlap
+    fn write(&mut self, addresses: AddressSet, value: u128) {
+        self.entries = self
+            .entries
+            .iter()
+            .flat_map(|&(set, old)| set.subtract(&addresses).into_iter().map(move |piece| (piece, old)))
+            .collect();
+        self.entries.push((addresses, value));
+    }
+
+    fn sum(&self) -> Option<u128> {
+        self.entries.iter().try_fold(0u128, |sum, &(set, value)| {
+            let total = match value {
+                0 => 0,
+                value => set.len()?.checked_mul(value)?,
+            };
+            sum.checked_add(total)
+        })
+    }
+
+    fn non_zero(&self) -> Vec<(AddressSet, u128)> {
+        let mut entries = self
+            .entries
+            .iter()
+            .filter(|&&(_, value)| value != 0)
+            .copied()
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|&(set, _)| (set.fixed, set.floating));
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AddressSet, DirectMemory, Memory, SparseMemory};
+
+    #[test]
+    fn test_subtract_address_sets() {
+        let set = AddressSet::new(0b0000, 0b0111);
+        let other = AddressSet::new(0b0010, 0b1001);
+
+        let pieces = set.subtract(&other);
+        assert_eq!(vec![AddressSet::new(0b0000, 0b0101), AddressSet::new(0b0110, 0b0001)], pieces);
+        for address in 0..16 {
+            let expected = set.contains(address) && !other.contains(address);
+            assert_eq!(expected, pieces.iter().any(|piece| piece.contains(address)), "{}", address);
+        }
+
+        assert_eq!(vec![set], set.subtract(&AddressSet::new(0b1000, 0)));
+        assert!(set.subtract(&AddressSet::new(0, 0b1111)).is_empty());
+    }
+
+    #[test]
+    fn test_sparse_memory_without_enumeration() {
+        let mut memory = SparseMemory::default();
+        memory.write(AddressSet::new(0, u128::MAX >> 92), 1);
+        memory.write(AddressSet::new(1, u128::MAX >> 93 << 1), 3);
+
+        assert_eq!(Some((1u128 << 35) + 3 * (1u128 << 35)), memory.sum());
+        assert_eq!(vec![(AddressSet::new(0, u128::MAX >> 93 << 1), 1), (AddressSet::new(1, u128::MAX >> 93 << 1), 3)], memory.non_zero());
+
+        memory.write(AddressSet::new(0, u128::MAX), 2);
+        assert_eq!(None, memory.sum());
+    }
+
+    #[test]
+    fn test_direct_memory() {
+        let mut memory = DirectMemory::default();
+        memory.write(AddressSet::new(8, 0), 11);
+        memory.write(AddressSet::new(7, 0), 101);
+        memory.write(AddressSet::new(8, 0), 0);
+        memory.write(AddressSet::new(16, 0b101), 2);
+
+        assert_eq!(Some(101 + 4 * 2), memory.sum());
+        assert_eq!(vec![(AddressSet::new(7, 0), 101), (AddressSet::new(16, 0), 2)], memory.non_zero()[..2].to_vec());
+        assert_eq!(5, memory.non_zero().len());
+    }
+}
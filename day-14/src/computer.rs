@@ -0,0 +1,201 @@
+use std::fmt::Write;
+
+use anyhow::anyhow;
+
+use crate::{
+    address::{AddressSet, DirectMemory, Memory, SparseMemory},
+    Instruction,
+};
+
+/// The largest supported word size in bits
+pub const MAX_WIDTH: u32 = 128;
+
+/// A bitmask, every bit is either forced to 1, forced to 0 or left floating (`X`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mask {
+    pub ones: u128,
+    pub zeros: u128,
+    pub floating: u128,
+}
+
+impl Mask {
+    /// Parses the mask, it needs exactly one character per bit of the word
+    pub fn parse(mask: &str, width: u32) -> anyhow::Result<Self> {
+        if mask.len() != width as usize {
+            return Err(anyhow!("Mask '{}' does not have {} bits", mask, width));
+        }
+
+        let bits = |bit: char| -> anyhow::Result<u128> {
+            let binary = mask.chars().map(|c| if c == bit { '1' } else { '0' }).collect::<String>();
+            Ok(u128::from_str_radix(&binary, 2)?)
+        };
+
+        if let Some(c) = mask.chars().find(|c| !matches!(c, '0' | '1' | 'X')) {
+            return Err(anyhow!("Invalid bit '{}' in mask '{}'", c, mask));
+        }
+        Ok(Self { ones: bits('1')?, zeros: bits('0')?, floating: bits('X')? })
+    }
+}
+
+/// Decides how the mask is applied to a memory write.
+///
+/// Returns the addresses to write to and the value to write, address and value fit into the word.
+pub trait Decoder {
+    fn decode(&self, mask: &Mask, address: u128, value: u128) -> (AddressSet, u128);
+
+    /// Returns the memory that suits the writes of the decoder
+    fn memory(&self) -> Box<dyn Memory> {
+        Box::new(SparseMemory::default())
+    }
+}
+
+/// The mask overwrites the bits of the value, `X` bits stay unchanged
+#[derive(Debug, Clone, Copy)]
+pub struct ValueDecoder;
+
+impl Decoder for ValueDecoder {
+    fn decode(&self, mask: &Mask, address: u128, value: u128) -> (AddressSet, u128) {
+        (AddressSet::new(address, 0), (value | mask.ones) & !mask.zeros)
+    }
+
+    /// Every write goes to a single address
+    fn memory(&self) -> Box<dyn Memory> {
+        Box::new(DirectMemory::default())
+    }
+}
+
+/// The mask sets the bits of the address, `X` bits float and write to both values of the bit
+#[derive(Debug, Clone, Copy)]
+pub struct AddressDecoder;
+
+impl Decoder for AddressDecoder {
+    fn decode(&self, mask: &Mask, address: u128, value: u128) -> (AddressSet, u128) {
+        (AddressSet::new(address | mask.ones, mask.floating), value)
+    }
+}
+
+/// Returns the decoder of the given chip version
+pub fn decoder(version: u32) -> anyhow::Result<Box<dyn Decoder>> {
+    match version {
+        1 => Ok(Box::new(ValueDecoder)),
+        2 => Ok(Box::new(AddressDecoder)),
+        _ => Err(anyhow!("Unknown decoder version {}", version)),
+    }
+}
+
+/// The docking program emulator with a word size of up to 128 bits
+pub struct DockingComputer {
+    width: u32,
+    mask: Option<Mask>,
+    memory: Box<dyn Memory>,
+    decoder: Box<dyn Decoder>,
+}
+
+impl DockingComputer {
+    pub fn new(width: u32, decoder: Box<dyn Decoder>) -> anyhow::Result<Self> {
+        if width == 0 || width > MAX_WIDTH {
+            return Err(anyhow!("Word size must be between 1 and {} bits", MAX_WIDTH));
+        }
+        Ok(Self { width, mask: None, memory: decoder.memory(), decoder })
+    }
+
+    /// Returns true if the number fits into the word
+    fn fits(&self, number: u128) -> bool {
+        number.checked_shr(self.width).unwrap_or(0) == 0
+    }
+
+    /// Executes a single instruction
+    pub fn execute(&mut self, instruction: &Instruction) -> anyhow::Result<()> {
+        match instruction {
+            Instruction::Mask(mask) => {
+                self.mask = Some(Mask::parse(mask, self.width)?);
+            }
+            Instruction::Mem(address, value) => {
+                let mask = self.mask.ok_or_else(|| anyhow!("No mask set before mem[{}]", address))?;
+                if !self.fits(*address) || !self.fits(*value) {
+                    return Err(anyhow!("mem[{}] = {} does not fit into {} bits", address, value, self.width));
+                }
+
+                let (addresses, value) = self.decoder.decode(&mask, *address, *value);
+                self.memory.write(addresses, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes all instructions
+    pub fn run(&mut self, instructions: &[Instruction]) -> anyhow::Result<()> {
+        instructions.iter().try_for_each(|instruction| self.execute(instruction))
+    }
+
+    /// Returns the sum of all values in memory
+    pub fn sum(&self) -> anyhow::Result<u128> {
+        self.memory.sum().ok_or_else(|| anyhow!("Sum of memory overflows"))
+    }
+
+    /// Lists all addresses with a non zero value, one line each.
+    ///
+    /// A floating address set is printed as pattern with `X` for every floating bit.
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        for (set, value) in self.memory.non_zero() {
+            if set.floating == 0 {
+                writeln!(out, "mem[{}] = {}", set.fixed, value).unwrap();
+            } else {
+                let pattern = (0..self.width)
+                    .rev()
+                    .map(|index| 1u128 << index)
+                    .map(|bit| if set.floating & bit != 0 { 'X' } else if set.fixed & bit != 0 { '1' } else { '0' })
+                    .collect::<String>();
+                writeln!(out, "mem[{}] = {}", pattern, value).unwrap();
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decoder, DockingComputer, Mask};
+    use crate::Instruction::{Mask as SetMask, Mem};
+
+    #[test]
+    fn test_parse_mask() {
+        let mask = Mask::parse("X10X", 4).unwrap();
+        assert_eq!(Mask { ones: 0b0100, zeros: 0b0010, floating: 0b1001 }, mask);
+        assert!(Mask::parse("X10X", 5).is_err());
+        assert!(Mask::parse("X1AX", 4).is_err());
+    }
+
+    #[test]
+    fn test_word_size() {
+        assert!(DockingComputer::new(0, decoder(1).unwrap()).is_err());
+        assert!(DockingComputer::new(129, decoder(1).unwrap()).is_err());
+        assert!(decoder(3).is_err());
+
+        let mut computer = DockingComputer::new(4, decoder(1).unwrap()).unwrap();
+        assert!(computer.execute(&Mem(1, 1)).is_err());
+        computer.execute(&SetMask("1XX0".into())).unwrap();
+        assert!(computer.execute(&Mem(16, 1)).is_err());
+        computer.execute(&Mem(15, 7)).unwrap();
+        assert_eq!(14, computer.sum().unwrap());
+    }
+
+    #[test]
+    fn test_wide_address_decoder() {
+        let mask = format!("X{}", "0".repeat(127));
+        let mut computer = DockingComputer::new(128, decoder(2).unwrap()).unwrap();
+        computer.run(&[SetMask(mask), Mem(u128::MAX >> 1, 3), Mem(1, 0)]).unwrap();
+
+        assert_eq!(6, computer.sum().unwrap());
+        assert_eq!(format!("mem[X{}] = 3\n", "1".repeat(127)), computer.dump());
+    }
+
+    #[test]
+    fn test_dump_skips_zero_values() {
+        let mut computer = DockingComputer::new(6, decoder(2).unwrap()).unwrap();
+        computer.run(&[SetMask("0000X1".into()), Mem(8, 5), Mem(1, 0), SetMask("000000".into()), Mem(32, 2)]).unwrap();
+
+        assert_eq!("mem[0010X1] = 5\nmem[32] = 2\n", computer.dump());
+    }
+}
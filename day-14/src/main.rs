@@ -1,10 +1,14 @@
-use itertools::Itertools;
-use std::collections::HashMap;
+mod address;
+mod computer;
+
+use std::convert::TryFrom;
+
+use computer::{decoder, DockingComputer};
 
 #[derive(Debug, PartialEq, Eq)]
 enum Instruction {
     Mask(String),
-    Mem(u64, u64),
+    Mem(u128, u128),
 }
 
 peg::parser!{
@@ -13,8 +17,10 @@ peg::parser!{
             = "mask = " bitmask:$(['X' | '0' | '1']+) { Instruction::Mask(bitmask.into()) }
 
         rule mem() -> Instruction
-            = "mem[" address:$(['0'..='9']+) "] = " value:$(['0'..='9']+)
-                { Instruction::Mem(address.parse::<u64>().unwrap(), value.parse::<u64>().unwrap()) }
+            = "mem[" address:number() "] = " value:number() { Instruction::Mem(address, value) }
+
+        rule number() -> u128
+            = n:$(['0'..='9']+) {? n.parse::<u128>().or(Err("128 bit number")) }
 
         pub(crate) rule line() -> Instruction
             = mem:mem() / mask:mask()
@@ -30,83 +36,31 @@ fn parse_input(content: &str) -> anyhow::Result<Vec<Instruction>> {
         .lines()
         .map(|line| line.trim())
         .filter(|&line| !line.is_empty())
-        .map(|line| parse_rule(line))
+        .map(parse_rule)
         .filter_map(Result::ok)
         .collect::<Vec<_>>();
 
     Ok(instructions)
 }
 
-fn run_instructions(instructions: &[Instruction]) -> anyhow::Result<u64> {
-    let mut and_mask = 0b11_1111_1111_1111_1111_1111_1111_1111_1111_1111u64;
-    let mut or_mask = 0b00_0000_0000_0000_0000_0000_0000_0000_0000_0000u64;
-
-    let mut memory: HashMap<u64, u64> = HashMap::new();
-
-    for instruction in instructions {
-        match instruction {
-            Instruction::Mask(mask) => {
-                and_mask = u64::from_str_radix(&mask.replace("X", "1"), 2)?;
-                or_mask = u64::from_str_radix(&mask.replace("X", "0"), 2)?;
-            }
-            Instruction::Mem(address, value) => {
-                let value = (value | or_mask) & and_mask;
-                memory.insert(*address, value);
-            }
-        }
-    }
+/// Runs the instructions on a 36 bit docking computer with the given decoder version
+fn run_docking_program(instructions: &[Instruction], version: u32) -> anyhow::Result<u64> {
+    let mut computer = DockingComputer::new(36, decoder(version)?)?;
+    computer.run(instructions)?;
+    Ok(u64::try_from(computer.sum()?)?)
+}
 
-    Ok(memory.values().sum())
+/// Runs the instructions with the value decoder, the mask overwrites bits of the values
+fn run_instructions(instructions: &[Instruction]) -> anyhow::Result<u64> {
+    run_docking_program(instructions, 1)
 }
 
-/// This function is a bit more complex than I'd like
+/// Runs the instructions with the memory address decoder, every `X` bit of the mask
+/// makes the write address float, it writes to both values of the bit.
+///
+/// The addresses are kept as sets, therefore the number of `X` bits does not matter.
 fn run_instructions_two(instructions: &[Instruction]) -> anyhow::Result<u64> {
-    let mut or_mask = 0b11_1111_1111_1111_1111_1111_1111_1111_1111_1111u64;
-    let mut mask = format!("{:036b}", 0);
-    let mut memory: HashMap<u64, u64> = HashMap::new();
-
-    for instruction in instructions {
-        match instruction {
-            Instruction::Mask(m) => {
-                or_mask = u64::from_str_radix(&m.replace("X", "1"), 2)?;
-                mask = m.clone();
-            }
-            Instruction::Mem(address, value) => {
-                // combine address with mask
-                let address = address | or_mask;
-                let address = format!("{:036b}", address);
-
-                // find all positions in the mask
-                let positions = address.chars()
-                    .zip(mask.chars())
-                    .map(|(l, r)| if r == 'X' { r } else { l })
-                    .enumerate()
-                    .filter(|(_index, c)| *c == 'X')
-                    .map(|(index, _)| index)
-                    .collect::<Vec<_>>();
-
-                // iterate over all combinations of the found 'x' positions
-                // find all possible addresses and set value
-                for bits in positions.iter().powerset() {
-                    let adr = address.chars()
-                        .enumerate()
-                        .map(|(index, c)| {
-                            if positions.contains(&index) {
-                                if bits.contains(&&index) { '1' } else { '0' }
-                            } else {
-                                c
-                            }
-                        })
-                        .collect::<String>();
-
-                    let adr = u64::from_str_radix(&adr, 2).unwrap();
-                    memory.insert(adr, *value);
-                }
-            }
-        }
-    }
-
-    Ok(memory.values().sum())
+    run_docking_program(instructions, 2)
 }
 
 fn main() -> anyhow::Result<()> {
@@ -117,6 +71,15 @@ fn main() -> anyhow::Result<()> {
     let result = run_instructions_two(&instructions)?;
     dbg!(result);
 
+    // `dump <version>` prints the memory after running the program
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(String::as_str) == Some("dump") {
+        let version = args.get(2).map_or(Ok(1), |v| v.parse::<u32>())?;
+        let mut computer = DockingComputer::new(36, decoder(version)?)?;
+        computer.run(&instructions)?;
+        print!("{}", computer.dump());
+    }
+
     Ok(())
 }
 
@@ -1,41 +1,148 @@
 use std::collections::HashMap;
 
-/// Find the sequence with given start numbers.
-fn find_sequence(starter: &[u64], turns: u64) -> Option<u64> {
-    let mut sequence = starter[0..starter.len() - 1]
-        .iter()
-        .enumerate()
-        .map(|(index, &value)| (value, index as u64))
-        .collect::<HashMap<u64, u64>>();
-
-    let mut last = *starter.last().unwrap();
-
-    for index in starter.len()-1..(turns-1) as usize {
-        last = if let Some((_, &i)) = sequence.get_key_value(&last) {
-            sequence.insert(last, index as u64);
-            index as u64 - i
-        } else {
-            sequence.insert(last, index as u64);
+use anyhow::anyhow;
+
+/// Statistics of a memory game so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stats {
+    /// The number of turns played
+    turns: u32,
+    /// The number of different numbers spoken
+    distinct: usize,
+    /// The largest number of turns between two turns speaking the same number
+    longest_gap: u32,
+}
+
+/// The elves' memory game, yields the spoken number of every turn.
+///
+/// The turn a number was spoken last is stored in a flat vector indexed by the number.
+/// After the start sequence a spoken number is never larger than the number of turns,
+/// only larger start numbers are kept in a map until the vector grows past them.
+#[derive(Debug, Clone)]
+struct MemoryGame {
+    start: Vec<u32>,
+    /// The last turn a number was spoken, 0 if it was never spoken
+    last_seen: Vec<u32>,
+    /// The last turn of spoken numbers beyond the vector
+    large: HashMap<u32, u32>,
+    turn: u32,
+    next: u32,
+    distinct: usize,
+    longest_gap: u32,
+}
+
+impl MemoryGame {
+    pub fn new(start: &[u32]) -> Self {
+        Self {
+            start: start.to_vec(),
+            last_seen: Vec::new(),
+            large: HashMap::new(),
+            turn: 0,
+            next: 0,
+            distinct: 0,
+            longest_gap: 0,
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            turns: self.turn,
+            distinct: self.distinct,
+            longest_gap: self.longest_gap,
+        }
+    }
+
+    /// Plays until the number is spoken and returns its turn, gives up after `limit` turns
+    pub fn find_first_turn(&mut self, number: u32, limit: u32) -> Option<u32> {
+        while self.turn < limit {
+            if self.next()? == number {
+                return Some(self.turn);
+            }
+        }
+        None
+    }
+}
+
+impl Iterator for MemoryGame {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start.is_empty() || self.turn == u32::MAX {
+            return None;
+        }
+
+        self.turn += 1;
+        let spoken = self.start.get(self.turn as usize - 1).copied().unwrap_or(self.next);
+
+        let index = spoken as usize;
+        if index >= self.last_seen.len() && spoken <= self.turn {
+            let len = (index + 1).next_power_of_two();
+            self.last_seen.resize(len, 0);
+
+            let last_seen = &mut self.last_seen;
+            self.large.retain(|&number, &mut turn| match last_seen.get_mut(number as usize) {
+                Some(slot) => {
+                    *slot = turn;
+                    false
+                }
+                None => true,
+            });
+        }
+
+        let slot = match self.last_seen.get_mut(index) {
+            Some(slot) => slot,
+            None => self.large.entry(spoken).or_insert(0),
+        };
+        let previous = std::mem::replace(slot, self.turn);
+        self.next = if previous == 0 {
+            self.distinct += 1;
             0
+        } else {
+            self.longest_gap = self.longest_gap.max(self.turn - previous);
+            self.turn - previous
         };
+
+        Some(spoken)
     }
+}
 
-    Some(last)
+/// Parses the comma separated start numbers
+fn parse_numbers(content: &str) -> anyhow::Result<Vec<u32>> {
+    content
+        .trim()
+        .split(',')
+        .map(|v| v.trim().parse::<u32>().map_err(|err| anyhow!("Failed to parse '{}': {}", v, err)))
+        .collect()
 }
 
-fn main() {
-    let input = vec![0, 12, 6, 13, 20, 1, 17];
+/// Find the sequence with given start numbers.
+fn find_sequence(starter: &[u32], turns: u32) -> Option<u32> {
+    MemoryGame::new(starter).nth(turns.checked_sub(1)? as usize)
+}
+
+fn main() -> anyhow::Result<()> {
+    let input = match std::env::args().nth(1) {
+        Some(arg) => parse_numbers(&arg)?,
+        None => vec![0, 12, 6, 13, 20, 1, 17],
+    };
 
     let result = find_sequence(&input, 2020);
     dbg!(result);
 
-    let result = find_sequence(&input, 30000000);
+    let mut game = MemoryGame::new(&input);
+    let result = game.nth(30000000 - 1);
     dbg!(result);
+    dbg!(game.stats());
+
+    let turn = MemoryGame::new(&input).find_first_turn(2020, 30000000);
+    dbg!(turn);
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::find_sequence;
+    use crate::{MemoryGame, Stats, find_sequence, parse_numbers};
 
     #[test]
     fn test_find_sequence() {
@@ -61,11 +168,61 @@ mod tests {
         assert_eq!(Some(1836), find_sequence(&[3, 1, 2], 2020));
     }
 
-    /// This test takes a bit longer, 30-60s in total
+    /// This test takes a bit longer, a few seconds in total
     #[test]
     fn test_find_very_long_sequences() {
         assert_eq!(Some(175594), find_sequence(&[0, 3, 6], 30000000));
         assert_eq!(Some(2578), find_sequence(&[1, 3, 2], 30000000));
         assert_eq!(Some(3544142), find_sequence(&[2, 1, 3], 30000000));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_memory_game_yields_every_turn() {
+        let spoken = MemoryGame::new(&[0, 3, 6]).take(10).collect::<Vec<_>>();
+        assert_eq!(vec![0, 3, 6, 0, 3, 3, 1, 0, 4, 0], spoken);
+        assert_eq!(None, MemoryGame::new(&[]).next());
+        assert_eq!(None, find_sequence(&[0, 3, 6], 0));
+    }
+
+    #[test]
+    fn test_large_start_numbers() {
+        // straightforward reference implementation
+        fn play(start: &[u32], turns: usize) -> Vec<u32> {
+            let mut spoken = start.to_vec();
+            while spoken.len() < turns {
+                let last = *spoken.last().unwrap();
+                let previous = spoken[..spoken.len() - 1].iter().rposition(|&n| n == last);
+                spoken.push(previous.map_or(0, |turn| (spoken.len() - 1 - turn) as u32));
+            }
+            spoken
+        }
+
+        let mut game = MemoryGame::new(&[4_000_000_000, 1]);
+        assert_eq!(vec![4_000_000_000, 1, 0, 0, 1, 3], game.by_ref().take(6).collect::<Vec<_>>());
+        assert!(game.last_seen.len() <= 8);
+
+        for start in [vec![4_000_000_000, 4_000_000_000], vec![5, 0], vec![40, 3, 12, 40, 0]].iter() {
+            assert_eq!(play(start, 200), MemoryGame::new(start).take(200).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_memory_game_stats() {
+        let mut game = MemoryGame::new(&[0, 3, 6]);
+        game.nth(9);
+        assert_eq!(Stats { turns: 10, distinct: 5, longest_gap: 4 }, game.stats());
+    }
+
+    #[test]
+    fn test_find_first_turn() {
+        assert_eq!(Some(9), MemoryGame::new(&[0, 3, 6]).find_first_turn(4, 2020));
+        assert_eq!(Some(2), MemoryGame::new(&[0, 3, 6]).find_first_turn(3, 2020));
+        assert_eq!(None, MemoryGame::new(&[0, 3, 6]).find_first_turn(4, 8));
+    }
+
+    #[test]
+    fn test_parse_numbers() {
+        assert_eq!(vec![0, 12, 6], parse_numbers("0,12,6\n").unwrap());
+        assert!(parse_numbers("0,-1").is_err());
+    }
+}
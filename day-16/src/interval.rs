@@ -0,0 +1,112 @@
+use std::{fmt::Debug, ops::RangeInclusive};
+
+/// A set of numbers stored as sorted, disjoint and non adjacent inclusive ranges
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<RangeInclusive<u64>>,
+}
+
+impl IntervalSet {
+    /// Sorts the ranges and merges all that overlap or touch, empty ranges are dropped
+    pub fn new<I>(ranges: I) -> Self
+    where
+        I: IntoIterator<Item = RangeInclusive<u64>>,
+    {
+        let mut sorted = ranges
+            .into_iter()
+            .filter(|range| !range.is_empty())
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|range| *range.start());
+
+        let mut ranges: Vec<RangeInclusive<u64>> = Vec::with_capacity(sorted.len());
+        for range in sorted {
+            match ranges.last_mut() {
+                Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                    *last = *last.start()..=*last.end().max(range.end());
+                }
+                _ => ranges.push(range),
+            }
+        }
+
+        Self { ranges }
+    }
+
+    /// Returns the union of all sets
+    pub fn union<'a, I>(sets: I) -> Self
+    where
+        I: IntoIterator<Item = &'a IntervalSet>,
+    {
+        Self::new(sets.into_iter().flat_map(|set| set.ranges.iter().cloned()))
+    }
+
+    /// Returns true if the value is in one of the ranges, by binary search
+    pub fn contains(&self, value: u64) -> bool {
+        let index = self.ranges.partition_point(|range| *range.start() <= value);
+        index > 0 && value <= *self.ranges[index - 1].end()
+    }
+
+    /// Returns the distance of the value to the closest range, 0 if it is contained.
+    /// Returns `None` for an empty set.
+    pub fn distance(&self, value: u64) -> Option<u64> {
+        let index = self.ranges.partition_point(|range| *range.start() <= value);
+        let below = index.checked_sub(1).map(|index| value.saturating_sub(*self.ranges[index].end()));
+        let above = self.ranges.get(index).map(|range| *range.start() - value);
+
+        match (below, above) {
+            (Some(below), Some(above)) => Some(below.min(above)),
+            (below, above) => below.or(above),
+        }
+    }
+}
+
+impl Debug for IntervalSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|range| match (*range.start(), *range.end()) {
+                (start, end) if start == end => format!("{}", start),
+                (start, u64::MAX) => format!("{}-", start),
+                (start, end) => format!("{}-{}", start, end),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", ranges.join(" or "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::RangeInclusive;
+
+    use super::IntervalSet;
+
+    #[test]
+    fn test_merge_ranges() {
+        let set = IntervalSet::new(vec![10..=12, 1..=3, 4..=5, 2..=3, 20..=20, RangeInclusive::new(7, 6)]);
+        assert_eq!(vec![1..=5, 10..=12, 20..=20], set.ranges);
+        assert_eq!("1-5 or 10-12 or 20", format!("{:?}", set));
+
+        let open = IntervalSet::new(vec![30..=u64::MAX, 0..=0]);
+        assert_eq!("0-5 or 10-12 or 20 or 30-", format!("{:?}", IntervalSet::union(&[open, set])));
+    }
+
+    #[test]
+    fn test_contains() {
+        let set = IntervalSet::new(vec![1..=3, 10..=u64::MAX, 6..=6]);
+        let contained = (0..12).filter(|&value| set.contains(value)).collect::<Vec<_>>();
+        assert_eq!(vec![1, 2, 3, 6, 10, 11], contained);
+        assert!(set.contains(u64::MAX));
+        assert!(!IntervalSet::default().contains(0));
+    }
+
+    #[test]
+    fn test_distance() {
+        let set = IntervalSet::new(vec![5..=10, 20..=30]);
+        assert_eq!(Some(5), set.distance(0));
+        assert_eq!(Some(0), set.distance(7));
+        assert_eq!(Some(4), set.distance(14));
+        assert_eq!(Some(1), set.distance(11));
+        assert_eq!(Some(70), set.distance(100));
+        assert_eq!(None, IntervalSet::default().distance(1));
+    }
+}
@@ -1,4 +1,12 @@
-use std::{fmt::Debug, collections::HashMap, ops::Range};
+mod interval;
+mod matching;
+mod report;
+
+use std::{fmt::Debug, collections::HashMap, ops::RangeInclusive};
+
+use interval::IntervalSet;
+use matching::{alternative, hall_violator, hopcroft_karp};
+use report::{decode_ticket, ValidationReport};
 
 type Ticket = Vec<u64>;
 
@@ -7,14 +15,16 @@ peg::parser!{
         rule number() -> u64
             = n:$(['0'..='9']+) { n.parse().unwrap() }
 
-        rule range() -> Range<u64>
-            = start:number() "-" end:number() { start..end + 1 }
+        /// a range `a-b`, an open-ended range `a-` or `-b`, or a single value `a`
+        rule range() -> RangeInclusive<u64>
+            = start:number()? "-" end:number()? { start.unwrap_or(0)..=end.unwrap_or(u64::MAX) }
+            / value:number() { value..=value }
 
         rule name() -> String
             = s:$(['a'..='z' | 'A'..='Z' | ' ']+) { s.into() }
 
         pub(crate) rule line() -> Rule
-            = name:name() ": " first:range() " or " second:range() { Rule::new(&name, first, second) }
+            = name:name() ": " ranges:(range() ++ " or ") { Rule::new(&name, ranges) }
     }
 }
 
@@ -22,24 +32,24 @@ peg::parser!{
 struct Rule {
     /// For now store the name of the rule (may be relevant later)
     pub name: String,
-    /// First range
-    pub first: Range<u64>,
-    /// Second range
-    pub second: Range<u64>,
+    /// All valid values, merged from the ranges of the rule
+    pub ranges: IntervalSet,
 }
 
 impl Rule {
-    pub fn new(name: &str, first: Range<u64>, second: Range<u64>) -> Self {
+    pub fn new<I>(name: &str, ranges: I) -> Self
+    where
+        I: IntoIterator<Item = RangeInclusive<u64>>,
+    {
         Self {
             name: name.into(),
-            first,
-            second,
+            ranges: IntervalSet::new(ranges),
         }
     }
 
-    /// Returns true if the given value is in first or second range
+    /// Returns true if the given value is in any of the ranges
     pub fn is_valid(&self, value: &u64) -> bool {
-        self.first.contains(value) || self.second.contains(value)
+        self.ranges.contains(*value)
     }
 
     /// Returns true if all the given numbers are valid for this rule
@@ -50,12 +60,34 @@ impl Rule {
 
 impl Debug for Rule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let first = format!("{}-{}", self.first.start, self.first.end);
-        let second = format!("{}-{}", self.second.start, self.second.end);
-        write!(f, "{}: {} or {}", self.name, first, second)
+        write!(f, "{}: {:?}", self.name, self.ranges)
     }
 }
 
+/// The reason the rules cannot be assigned to the columns of the tickets
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AssignmentError {
+    /// The rules only fit into fewer columns than there are rules
+    Infeasible { rules: Vec<String>, columns: Vec<usize> },
+    /// The rule fits into either column, both lead to a valid assignment
+    Ambiguous { rule: String, columns: (usize, usize) },
+}
+
+impl std::fmt::Display for AssignmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssignmentError::Infeasible { rules, columns } => {
+                write!(f, "Rules {:?} only fit into columns {:?}", rules, columns)
+            }
+            AssignmentError::Ambiguous { rule, columns } => {
+                write!(f, "Rule '{}' fits into column {} or {}", rule, columns.0, columns.1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssignmentError {}
+
 #[derive(Debug, Default)]
 struct TicketValidator {
     /// The list of rules
@@ -64,33 +96,34 @@ struct TicketValidator {
     pub my_ticket: Vec<u64>,
     /// The list of all nearby tickets
     pub nearby_tickets: Vec<Vec<u64>>,
+    /// The line number of every nearby ticket in the input, starting at 1
+    pub nearby_lines: Vec<usize>,
+    /// The values that are valid for any rule, merged from all rules
+    pub valid_values: IntervalSet,
 }
 
+#[derive(Default)]
 enum ReadState {
+    #[default]
     Rule,
     YourTicket,
     NearbyTickets,
 }
 
-impl Default for ReadState {
-    fn default() -> Self {
-        Self::Rule
-    }
-}
-
 impl TicketValidator {
     pub fn parse(content: &str) -> anyhow::Result<Self> {
         let lines = content
             .lines()
             .map(|line| line.trim())
-            .filter(|&line| !line.is_empty())
+            .enumerate()
+            .filter(|&(_, line)| !line.is_empty())
             .collect::<Vec<_>>();
 
         let mut validator = Self::default();
 
         // first a list of rules are given until the line "your ticket:" appears
         let mut state = ReadState::default();
-        for line in lines {
+        for (index, line) in lines {
             if line.starts_with("your ticket:") {
                 state = ReadState::YourTicket;
                 continue;
@@ -102,10 +135,14 @@ impl TicketValidator {
             match state {
                 ReadState::Rule => validator.rules.push(line_parser::line(line)?),
                 ReadState::YourTicket => validator.my_ticket = Self::parse_ticket(line)?,
-                ReadState::NearbyTickets => validator.nearby_tickets.push(Self::parse_ticket(line)?),
+                ReadState::NearbyTickets => {
+                    validator.nearby_tickets.push(Self::parse_ticket(line)?);
+                    validator.nearby_lines.push(index + 1);
+                }
             }
         }
 
+        validator.valid_values = IntervalSet::union(validator.rules.iter().map(|rule| &rule.ranges));
         Ok(validator)
     }
 
@@ -134,50 +171,41 @@ impl TicketValidator {
         result
     }
 
-    /// Detects all valid tickets, reverse map numbers to rules
-    pub fn map_valid_rules(&self) -> HashMap<usize, Rule> {
-        // get list of all valid tickets
-        let valid_tickets = self.find_valid_tickets();
-
-        // flip numbers from rows to columns, map index by group of numbers
-        let mut mapped_numbers = Self::flip_rows_to_cols(&valid_tickets)
+    /// Assigns every rule to the column of the valid tickets it belongs to, returns the
+    /// column index per rule name.
+    ///
+    /// The assignment is a maximum bipartite matching between rules and columns, where a rule
+    /// is connected to every column whose numbers are all valid for it. It fails if not every rule
+    /// finds a column or if there is more than one valid assignment.
+    pub fn map_valid_rules(&self) -> Result<HashMap<String, usize>, AssignmentError> {
+        let columns = Self::flip_rows_to_cols(&self.find_valid_tickets());
+
+        let adjacency = self
+            .rules
             .iter()
-            .enumerate()
-            .map(|(index, numbers)| (index, numbers.clone()))
-            .collect::<HashMap<usize, Vec<u64>>>();
-
-        // check all rules, pick the set of numbers for which only one rule applies, then remove set
-        // this should eliminate all possible multiple candidate sets until only one rule applies
-        let mut result = HashMap::new();
-        let mut rules = Vec::new();
-
-        // find the best candidate for every rule
-        // first find the only matching candidate, then mark it as seen
-        // remove the candidates from the mapped numbers list
-        loop {
-            for index in 0..self.rules.len() {
-                let rule = self.rules.get(index).unwrap();
-
-                let candidates = mapped_numbers
-                    .iter()
-                    .filter(|(_i, numbers)| rule.valid_numbers(numbers))
-                    .collect::<Vec<_>>();
-    
-                // there may be multiple candidates, only consider the single matching one
-                if candidates.len() == 1 {
-                    let i = *candidates.first().unwrap().0;
-                    mapped_numbers.remove(&i);
-                    rules.push(i);
-                    result.insert(i, (*rule).clone());
-                }
-            }
+            .map(|rule| {
+                (0..columns.len())
+                    .filter(|&column| rule.valid_numbers(&columns[column]))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
 
-            if mapped_numbers.is_empty() {
-                break;
-            }
+        let matching = hopcroft_karp(&adjacency, columns.len());
+        if let Some((rules, columns)) = hall_violator(&adjacency, &matching) {
+            let rules = rules.iter().map(|&index| self.rules[index].name.clone()).collect();
+            return Err(AssignmentError::Infeasible { rules, columns });
+        }
+        if let Some((rule, current, other)) = alternative(&adjacency, &matching) {
+            let rule = self.rules[rule].name.clone();
+            return Err(AssignmentError::Ambiguous { rule, columns: (current, other) });
         }
 
-        result
+        Ok(self
+            .rules
+            .iter()
+            .zip(matching.left)
+            .filter_map(|(rule, column)| column.map(|column| (rule.name.clone(), column)))
+            .collect())
     }
 
     /// Detect all valid tickets
@@ -219,9 +247,7 @@ impl TicketValidator {
 
     /// Returns true if the given value is valid in any of the rules
     fn is_valid(&self, value: u64) -> bool {
-        self.rules
-            .iter()
-            .any(|rule| rule.is_valid(&value))
+        self.valid_values.contains(value)
     }
 }
 
@@ -232,17 +258,27 @@ fn main() -> anyhow::Result<()> {
     let result = validator.find_invalid_sum();
     dbg!(&result);
 
-    let mapped_rules = validator.map_valid_rules();
+    let mapped_rules = validator.map_valid_rules()?;
     dbg!(&mapped_rules);
 
     // find all rules with prefix "departure"
     let product = mapped_rules
         .iter()
-        .filter(|(_, rule)| rule.name.starts_with("departure"))
-        .map(|(&index, _)| validator.my_ticket[index as usize])
+        .filter(|(name, _)| name.starts_with("departure"))
+        .map(|(_, &index)| validator.my_ticket[index])
         .product::<u64>();
     dbg!(product);
 
+    dbg!(decode_ticket(&validator.my_ticket, &mapped_rules));
+
+    // `report` prints the validation report of the nearby tickets as CSV
+    let report = ValidationReport::new(&validator);
+    if std::env::args().nth(1).as_deref() == Some("report") {
+        print!("{}", report.to_csv());
+    } else {
+        dbg!(report.invalid_tickets().count());
+    }
+
     Ok(())
 }
 
@@ -250,7 +286,7 @@ fn main() -> anyhow::Result<()> {
 mod tests {
     use std::collections::HashMap;
 
-    use crate::{Rule, TicketValidator};
+    use crate::{AssignmentError, Rule, TicketValidator, line_parser};
 
     const CONTENT: &str = r#"
         class: 1-3 or 5-7
@@ -269,7 +305,18 @@ mod tests {
 
     #[test]
     fn test_rule_debug_format() {
-        assert_eq!("rule: 1-3 or 5-7", format!("{:?}", Rule::new("rule", 1..3, 5..7)))
+        assert_eq!("rule: 1-3 or 5-7", format!("{:?}", Rule::new("rule", vec![1..=3, 5..=7])))
+    }
+
+    #[test]
+    fn test_parse_rule_ranges() {
+        let rule = line_parser::line("gate: 5-7 or 1-2 or 9 or 3-4 or 30-").unwrap();
+        assert_eq!("gate: 1-7 or 9 or 30-", format!("{:?}", rule));
+        assert!(rule.is_valid(&u64::MAX));
+        assert!(!rule.is_valid(&8));
+
+        let rule = line_parser::line("lower: -10").unwrap();
+        assert_eq!(Rule::new("lower", vec![0..=10]), rule);
     }
 
     #[test]
@@ -349,12 +396,53 @@ mod tests {
         let rules = validator.map_valid_rules();
         // dbg!(&rules);
 
-        let expected_rules: HashMap<usize, Rule> = vec![
-            (0, Rule::new("row", 0..6, 8..20)),
-            (1, Rule::new("class", 0..2, 4..20)),
-            (2, Rule::new("seat", 0..14, 16..20)),
+        let expected_rules: HashMap<String, usize> = vec![
+            ("row".into(), 0),
+            ("class".into(), 1),
+            ("seat".into(), 2),
         ].into_iter().collect();
 
-        assert_eq!(expected_rules, rules);
+        assert_eq!(Ok(expected_rules), rules);
+    }
+
+    #[test]
+    fn test_infeasible_ticket_fields() {
+        let content = r#"
+            class: 1-1 or 9-14
+            row: 1-1 or 9-14
+            seat: 0-13 or 16-19
+
+            your ticket:
+            11,12,13
+
+            nearby tickets:
+            3,9,18
+            15,1,5
+            5,14,9
+        "#;
+
+        let validator = TicketValidator::parse(content).unwrap();
+        let expected = AssignmentError::Infeasible { rules: vec!["class".into(), "row".into()], columns: vec![1] };
+        assert_eq!(Err(expected), validator.map_valid_rules());
+    }
+
+    #[test]
+    fn test_ambiguous_ticket_fields() {
+        let content = r#"
+            class: 0-19 or 20-20
+            row: 0-19 or 20-20
+            seat: 0-13 or 16-19
+
+            your ticket:
+            11,12,13
+
+            nearby tickets:
+            3,9,18
+            15,1,5
+            5,14,9
+        "#;
+
+        let validator = TicketValidator::parse(content).unwrap();
+        assert!(matches!(validator.map_valid_rules(), Err(AssignmentError::Ambiguous { .. })));
     }
 }
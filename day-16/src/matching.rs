@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+
+/// A maximum matching of a bipartite graph, the partner of every left and right vertex
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matching {
+    pub left: Vec<Option<usize>>,
+    pub right: Vec<Option<usize>>,
+}
+
+/// Finds a maximum matching with the Hopcroft-Karp algorithm.
+///
+/// The adjacency lists the right vertices of every left vertex.
+pub fn hopcroft_karp(adjacency: &[Vec<usize>], right_len: usize) -> Matching {
+    let mut search = Search {
+        adjacency,
+        matching: Matching { left: vec![None; adjacency.len()], right: vec![None; right_len] },
+        layers: vec![usize::MAX; adjacency.len()],
+    };
+
+    while search.build_layers() {
+        for vertex in 0..adjacency.len() {
+            if search.matching.left[vertex].is_none() {
+                search.augment(vertex);
+            }
+        }
+    }
+
+    search.matching
+}
+
+struct Search<'a> {
+    adjacency: &'a [Vec<usize>],
+    matching: Matching,
+    /// The length of the shortest alternating path from a free left vertex
+    layers: Vec<usize>,
+}
+
+impl Search<'_> {
+    /// Layers the left vertices by breadth first search, returns true if an augmenting path exists
+    fn build_layers(&mut self) -> bool {
+        let mut queue = VecDeque::new();
+        for (vertex, partner) in self.matching.left.iter().enumerate() {
+            self.layers[vertex] = match partner {
+                None => {
+                    queue.push_back(vertex);
+                    0
+                }
+                Some(_) => usize::MAX,
+            };
+        }
+
+        let mut found = false;
+        while let Some(vertex) = queue.pop_front() {
+            for &right in &self.adjacency[vertex] {
+                match self.matching.right[right] {
+                    None => found = true,
+                    Some(next) if self.layers[next] == usize::MAX => {
+                        self.layers[next] = self.layers[vertex] + 1;
+                        queue.push_back(next);
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        found
+    }
+
+    /// Follows the layers depth first to a free right vertex and flips the path
+    fn augment(&mut self, vertex: usize) -> bool {
+        for index in 0..self.adjacency[vertex].len() {
+            let right = self.adjacency[vertex][index];
+            let free = match self.matching.right[right] {
+                None => true,
+                Some(next) => self.layers[next] == self.layers[vertex] + 1 && self.augment(next),
+            };
+
+            if free {
+                self.matching.left[vertex] = Some(right);
+                self.matching.right[right] = Some(vertex);
+                return true;
+            }
+        }
+
+        self.layers[vertex] = usize::MAX;
+        false
+    }
+}
+
+/// Returns the left vertices that cannot be matched together with their combined neighbors,
+/// there are fewer neighbors than vertices. Returns `None` if the matching is complete.
+pub fn hall_violator(adjacency: &[Vec<usize>], matching: &Matching) -> Option<(Vec<usize>, Vec<usize>)> {
+    let mut left = vec![false; adjacency.len()];
+    let mut right = vec![false; matching.right.len()];
+
+    let mut queue = (0..adjacency.len())
+        .filter(|&vertex| matching.left[vertex].is_none())
+        .collect::<VecDeque<_>>();
+    if queue.is_empty() {
+        return None;
+    }
+
+    while let Some(vertex) = queue.pop_front() {
+        left[vertex] = true;
+        for &neighbor in &adjacency[vertex] {
+            if !right[neighbor] {
+                right[neighbor] = true;
+                if let Some(next) = matching.right[neighbor] {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    let collect = |visited: Vec<bool>| visited.iter().enumerate().filter(|(_, &v)| v).map(|(i, _)| i).collect();
+    Some((collect(left), collect(right)))
+}
+
+/// Finds a different matching of the same size, returns `(left, current, other)` for the first
+/// left vertex that can be matched to another right vertex instead.
+pub fn alternative(adjacency: &[Vec<usize>], matching: &Matching) -> Option<(usize, usize, usize)> {
+    // an alternating path from the left vertex either returns to its current partner
+    // or ends in a free right vertex, both free the current partner without losing a pair
+    fn reaches(adjacency: &[Vec<usize>], matching: &Matching, right: usize, target: usize, visited: &mut [bool]) -> bool {
+        if visited[right] {
+            return false;
+        }
+        visited[right] = true;
+
+        match matching.right[right] {
+            _ if right == target => true,
+            None => true,
+            Some(next) => adjacency[next]
+                .iter()
+                .any(|&neighbor| reaches(adjacency, matching, neighbor, target, visited)),
+        }
+    }
+
+    for (vertex, partner) in matching.left.iter().enumerate() {
+        let current = match partner {
+            Some(current) => *current,
+            None => continue,
+        };
+
+        let mut visited = vec![false; matching.right.len()];
+        for &other in adjacency[vertex].iter().filter(|&&other| other != current) {
+            if reaches(adjacency, matching, other, current, &mut visited) {
+                return Some((vertex, current, other));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{alternative, hall_violator, hopcroft_karp};
+
+    #[test]
+    fn test_maximum_matching() {
+        let adjacency = vec![vec![0, 1], vec![0], vec![1, 2]];
+        let matching = hopcroft_karp(&adjacency, 3);
+
+        assert_eq!(vec![Some(1), Some(0), Some(2)], matching.left);
+        assert_eq!(None, hall_violator(&adjacency, &matching));
+        assert_eq!(None, alternative(&adjacency, &matching));
+    }
+
+    #[test]
+    fn test_infeasible_matching() {
+        let adjacency = vec![vec![0], vec![0], vec![0, 1, 2]];
+        let matching = hopcroft_karp(&adjacency, 3);
+
+        assert_eq!(vec![Some(0), None, Some(1)], matching.left);
+        assert_eq!(Some((vec![0, 1], vec![0])), hall_violator(&adjacency, &matching));
+    }
+
+    #[test]
+    fn test_ambiguous_matching() {
+        let adjacency = vec![vec![0, 1], vec![0, 1], vec![2]];
+        let matching = hopcroft_karp(&adjacency, 3);
+        assert_eq!(Some((0, 0, 1)), alternative(&adjacency, &matching));
+
+        let adjacency = vec![vec![0, 1], vec![1]];
+        let matching = hopcroft_karp(&adjacency, 2);
+        assert_eq!(None, alternative(&adjacency, &matching));
+
+        // a spare right vertex also allows a different matching
+        let matching = hopcroft_karp(&[vec![0, 2], vec![1]], 3);
+        assert_eq!(Some((0, 0, 2)), alternative(&[vec![0, 2], vec![1]], &matching));
+    }
+}
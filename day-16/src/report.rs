@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use crate::TicketValidator;
+
+/// A value of a nearby ticket that is not valid for any rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidValue {
+    pub column: usize,
+    pub value: u64,
+    /// The rule that misses the value by the smallest distance, together with the distance
+    pub closest_rule: Option<(String, u64)>,
+}
+
+/// The diagnostics of a single nearby ticket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TicketReport {
+    /// The line number of the ticket in the input, starting at 1
+    pub line: usize,
+    pub invalid: Vec<InvalidValue>,
+}
+
+impl TicketReport {
+    pub fn is_valid(&self) -> bool {
+        self.invalid.is_empty()
+    }
+}
+
+/// The diagnostics of all nearby tickets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub tickets: Vec<TicketReport>,
+}
+
+/// Quotes the field if it contains a separator or a quote
+fn csv_field(field: &str) -> String {
+    if field.contains([',', ';', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.into()
+    }
+}
+
+impl ValidationReport {
+    pub fn new(validator: &TicketValidator) -> Self {
+        let tickets = validator
+            .nearby_tickets
+            .iter()
+            .zip(&validator.nearby_lines)
+            .map(|(ticket, &line)| {
+                let invalid = ticket
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &value)| !validator.valid_values.contains(value))
+                    .map(|(column, &value)| InvalidValue {
+                        column,
+                        value,
+                        closest_rule: validator
+                            .rules
+                            .iter()
+                            .filter_map(|rule| rule.ranges.distance(value).map(|distance| (rule.name.clone(), distance)))
+                            .min_by_key(|(_, distance)| *distance),
+                    })
+                    .collect();
+                TicketReport { line, invalid }
+            })
+            .collect();
+
+        Self { tickets }
+    }
+
+    /// Returns the reports of all tickets with at least one invalid value
+    pub fn invalid_tickets(&self) -> impl Iterator<Item = &TicketReport> {
+        self.tickets.iter().filter(|ticket| !ticket.is_valid())
+    }
+
+    /// Exports the report with one row per ticket, lists of values are separated by `;`
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("line,valid,invalid_columns,invalid_values,closest_rules\n");
+
+        for ticket in &self.tickets {
+            let join = |field: &dyn Fn(&InvalidValue) -> String| {
+                ticket.invalid.iter().map(field).collect::<Vec<_>>().join(";")
+            };
+
+            let columns = join(&|invalid| invalid.column.to_string());
+            let values = join(&|invalid| invalid.value.to_string());
+            let rules = join(&|invalid| match &invalid.closest_rule {
+                Some((name, distance)) => format!("{} ({})", name, distance),
+                None => String::new(),
+            });
+
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                ticket.line,
+                ticket.is_valid(),
+                csv_field(&columns),
+                csv_field(&values),
+                csv_field(&rules),
+            ));
+        }
+
+        csv
+    }
+}
+
+/// Returns the field names and values of the ticket in column order, given the assigned columns
+pub fn decode_ticket(ticket: &[u64], assignment: &HashMap<String, usize>) -> Vec<(String, u64)> {
+    let mut fields = assignment
+        .iter()
+        .filter(|(_, &column)| column < ticket.len())
+        .map(|(name, &column)| (column, name.clone(), ticket[column]))
+        .collect::<Vec<_>>();
+    fields.sort_unstable();
+
+    fields.into_iter().map(|(_, name, value)| (name, value)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_ticket, InvalidValue, ValidationReport};
+    use crate::TicketValidator;
+
+    const CONTENT: &str = r#"class: 1-3 or 5-7
+row: 6-11 or 33-44
+seat: 13-40 or 45-50
+
+your ticket:
+7,1,14
+
+nearby tickets:
+7,3,47
+40,4,50
+55,2,20
+38,6,12"#;
+
+    #[test]
+    fn test_validation_report() {
+        let validator = TicketValidator::parse(CONTENT).unwrap();
+        let report = ValidationReport::new(&validator);
+
+        assert_eq!(vec![9, 10, 11, 12], report.tickets.iter().map(|t| t.line).collect::<Vec<_>>());
+        assert_eq!(3, report.invalid_tickets().count());
+        assert_eq!(
+            vec![InvalidValue { column: 1, value: 4, closest_rule: Some(("class".into(), 1)) }],
+            report.tickets[1].invalid,
+        );
+        assert_eq!(Some(("seat".into(), 5)), report.tickets[2].invalid[0].closest_rule);
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let validator = TicketValidator::parse("class: 1-3\n\nnearby tickets:\n1,2\n4,9,3").unwrap();
+        let csv = ValidationReport::new(&validator).to_csv();
+
+        let expected = "line,valid,invalid_columns,invalid_values,closest_rules\n\
+            4,true,,,\n\
+            5,false,\"0;1\",\"4;9\",\"class (1);class (6)\"\n";
+        assert_eq!(expected, csv);
+    }
+
+    #[test]
+    fn test_decode_ticket() {
+        let assignment = vec![("row".into(), 0), ("seat".into(), 2), ("class".into(), 1)].into_iter().collect();
+        let expected = vec![("row".into(), 11), ("class".into(), 12), ("seat".into(), 13)];
+        assert_eq!(expected, decode_ticket(&[11, 12, 13], &assignment));
+    }
+}
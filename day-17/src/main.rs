@@ -1,4 +1,8 @@
-use std::fmt::Debug;
+mod render;
+
+use std::collections::HashSet;
+
+use automaton::SparseAutomaton;
 
 /// the input grid
 const INPUT: &str = r#"
@@ -12,169 +16,216 @@ const INPUT: &str = r#"
     #.......
 "#;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Cube {
-    pub x: i32,
-    pub y: i32,
-    pub z: i32,
-    pub w: i32,
-}
+/// The coordinates of a cube, `x` and `y` first, followed by the extra dimensions
+type Cube<const D: usize> = [i32; D];
 
-#[derive(Debug, Clone)]
-struct Grid {
-    pub cubes: Vec<Cube>,
+/// The active cubes of a `D` dimensional pocket dimension.
+///
+/// A symmetric grid is mirror-symmetric in every extra dimension, it only stores the cubes
+/// whose extra coordinates are all non-negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Grid<const D: usize> {
+    pub cubes: HashSet<Cube<D>>,
+    pub symmetric: bool,
 }
 
-impl Grid {
+impl<const D: usize> Grid<D> {
+    /// Parses the initial slice, all extra dimensions are 0
     pub fn parse(content: &str) -> anyhow::Result<Self> {
+        if D < 2 {
+            return Err(anyhow::anyhow!("Grid needs at least 2 dimensions, got {}", D));
+        }
+
         let lines = content
             .lines()
             .map(str::trim)
             .filter(|&line| !line.is_empty())
             .collect::<Vec<_>>();
 
-        let mut cubes = Vec::new();
+        let mut cubes = HashSet::new();
         for (y, &row) in lines.iter().enumerate() {
             for (x, c) in row.chars().enumerate() {
                 if c == '#' {
-                    cubes.push(Cube{ x: x as i32, y: y as i32, z: 1, w: 0 });
+                    let mut cube = [0; D];
+                    cube[0] = x as i32;
+                    cube[1] = y as i32;
+                    cubes.insert(cube);
                 }
             }
         }
 
-        Ok(Self { cubes })
-    }
-
-    pub fn min_x(&self) -> i32 {
-        self.cubes.iter().map(|cube| cube.x).min().unwrap_or(0)
-    }
-
-    pub fn max_x(&self) -> i32 {
-        self.cubes.iter().map(|cube| cube.x).max().unwrap_or(0)
+        Ok(Self { cubes, symmetric: false })
     }
 
-    pub fn min_y(&self) -> i32 {
-        self.cubes.iter().map(|cube| cube.y).min().unwrap_or(0)
-    }
-
-    pub fn max_y(&self) -> i32 {
-        self.cubes.iter().map(|cube| cube.y).max().unwrap_or(0)
-    }
-
-    pub fn min_z(&self) -> i32 {
-        self.cubes.iter().map(|cube| cube.z).min().unwrap_or(0)
+    /// Switches to the symmetric mode, that only simulates the non-negative half-space of the
+    /// extra dimensions. The grid must be flat in all extra dimensions.
+    pub fn into_symmetric(self) -> anyhow::Result<Self> {
+        if self.cubes.iter().any(|cube| cube[2..].iter().any(|&c| c != 0)) {
+            return Err(anyhow::anyhow!("Grid is not flat in the extra dimensions"));
+        }
+        Ok(Self { symmetric: true, ..self })
     }
 
-    pub fn max_z(&self) -> i32 {
-        self.cubes.iter().map(|cube| cube.z).max().unwrap_or(0)
+    /// Returns all mirror images of the cube that are stored, including the cube itself
+    fn mirror_images(cube: &Cube<D>, mut visit: impl FnMut(Cube<D>)) {
+        let axes = (2..D).filter(|&axis| cube[axis] != 0).collect::<Vec<_>>();
+        for signs in 0..1u32 << axes.len() {
+            let mut image = *cube;
+            for (bit, &axis) in axes.iter().enumerate() {
+                if signs & (1 << bit) != 0 {
+                    image[axis] = -image[axis];
+                }
+            }
+            visit(image);
+        }
     }
 
-    pub fn min_w(&self) -> i32 {
-        self.cubes.iter().map(|cube| cube.w).min().unwrap_or(0)
-    }
+    /// Returns the full grid, all mirror images of a symmetric grid are added
+    pub fn unfold(&self) -> Self {
+        if !self.symmetric {
+            return self.clone();
+        }
 
-    pub fn max_w(&self) -> i32 {
-        self.cubes.iter().map(|cube| cube.w).max().unwrap_or(0)
+        let mut cubes = HashSet::new();
+        for cube in &self.cubes {
+            Self::mirror_images(cube, |image| {
+                cubes.insert(image);
+            });
+        }
+        Self { cubes, symmetric: false }
     }
 
-    /// Returns the cube at 3-dimensional coordinates
-    pub fn cube(&self, x: i32, y: i32, z: i32, w: i32) -> Option<&Cube> {
-        self.cubes
-            .iter()
-            .find(|&cube| cube.x == x && cube.y == y && cube.z == z && cube.w == w)
+    /// Returns the offsets to all `3^D - 1` neighbors
+    fn offsets() -> Vec<Cube<D>> {
+        let mut offsets = vec![[0; D]];
+        for axis in 0..D {
+            offsets = offsets
+                .into_iter()
+                .flat_map(|offset| {
+                    (-1..=1).map(move |delta| {
+                        let mut offset = offset;
+                        offset[axis] = delta;
+                        offset
+                    })
+                })
+                .collect();
+        }
+        offsets.retain(|offset| offset.iter().any(|&delta| delta != 0));
+        offsets
     }
 
-    /// Conway cycle
-    pub fn cycle_3d(grid: &Grid, num_cycles: u32) -> anyhow::Result<Grid> {
-        let mut grid = grid.clone();
-
-        for _ in 0..num_cycles {
-            let mut cubes = Vec::new();
-
-            for x in grid.min_x()-1..grid.max_x()+2 {
-                for y in grid.min_y()-1..grid.max_y()+2 {
-                    for z in grid.min_z()-1..grid.max_z()+2 {
-                        let neighbors = grid.neighbors(x, y, z, 0);
-
-                        if grid.cube(x, y, z, 0).is_some() {
-                            if neighbors == 2 || neighbors == 3 {
-                                cubes.push(Cube{ x, y, z, w: 0 });
-                            }
-                        } else if neighbors == 3 {
-                            cubes.push(Cube{ x, y, z, w: 0 });
-                        }
-                    }
+    /// Visits all neighbors of an active cube.
+    ///
+    /// In symmetric mode only cubes of the half-space are visited. Their neighbors outside of it
+    /// are the mirror images with an extra coordinate of -1, therefore each cube with a 1 in
+    /// an extra dimension also visits the neighbors of its mirror image.
+    fn visit_neighbors(&self, cube: &Cube<D>, offsets: &[Cube<D>], visit: &mut dyn FnMut(Cube<D>)) {
+        let mut visit_offsets = |cube: Cube<D>| {
+            for offset in offsets {
+                let mut neighbor = cube;
+                for axis in 0..D {
+                    neighbor[axis] += offset[axis];
+                }
+                if !self.symmetric || neighbor[2..].iter().all(|&c| c >= 0) {
+                    visit(neighbor);
                 }
             }
+        };
 
-            grid = Grid{ cubes };
+        if self.symmetric {
+            let mut near = *cube;
+            for c in near[2..].iter_mut() {
+                *c = if *c == 1 { 1 } else { 0 };
+            }
+            Self::mirror_images(&near, |image| {
+                let mut source = *cube;
+                for axis in 2..D {
+                    if image[axis] < 0 {
+                        source[axis] = -1;
+                    }
+                }
+                visit_offsets(source);
+            });
+        } else {
+            visit_offsets(*cube);
         }
-
-        Ok(grid)
     }
 
-    pub fn cycle_4d(grid: &Grid, num_cycles: u32) -> anyhow::Result<Grid> {
-        let mut grid = grid.clone();
-
-        for _ in 0..num_cycles {
-            let mut cubes = Vec::new();
-
-            for x in grid.min_x()-1..grid.max_x()+2 {
-                for y in grid.min_y()-1..grid.max_y()+2 {
-                    for z in grid.min_z()-1..grid.max_z()+2 {
-                        for w in grid.min_w()-1..grid.max_w()+2 {
-                            let neighbors = grid.neighbors(x, y, z, w);
-
-                            if grid.cube(x, y, z, w).is_some() {
-                                if neighbors == 2 || neighbors == 3 {
-                                    cubes.push(Cube{ x, y, z, w });
-                                }
-                            } else if neighbors == 3 {
-                                cubes.push(Cube{ x, y, z, w });
-                            }
-                        }
-                    }
-                }
-            }
+    /// Conway cycle, an active cube stays active with 2 or 3 active neighbors,
+    /// an inactive cube becomes active with exactly 3 active neighbors.
+    pub fn cycle(&self, num_cycles: u32) -> Self {
+        let offsets = Self::offsets();
+        let neighborhood = |cube: &Cube<D>, visit: &mut dyn FnMut(Cube<D>)| self.visit_neighbors(cube, &offsets, visit);
+        let life = |active: bool, count: usize| count == 3 || (active && count == 2);
 
-            grid = Grid{ cubes };
-        }
+        let mut automaton = SparseAutomaton::new(self.cubes.clone(), neighborhood, life);
+        automaton.run(num_cycles as usize);
 
-        Ok(grid)
+        Grid { cubes: automaton.into_cells(), symmetric: self.symmetric }
     }
 
-    /// Returns the number of active cells
+    /// Returns the number of active cells, in symmetric mode including all mirror images
     pub fn num_active(&self) -> usize {
-        self.cubes.len()
+        if !self.symmetric {
+            return self.cubes.len();
+        }
+
+        self.cubes
+            .iter()
+            .map(|cube| 1 << cube[2..].iter().filter(|&&c| c != 0).count())
+            .sum()
     }
 
     /// Returns the number of active neighbors
-    pub fn neighbors(&self, x: i32, y: i32, z: i32, w: i32) -> u64 {
-        let mut neighbors = 0;
-        for dx in -1..=1 {
-            for dy in -1..=1 {
-                for dz in -1..=1 {
-                    for dw in -1..=1 {
-                        if (dx != 0 || dy != 0 || dz != 0 || dw != 0) && self.cube(x + dx, y + dy, z + dz, w + dw).is_some() {
-                            neighbors += 1;
-                        }
-                    }
+    #[cfg(test)]
+    pub fn neighbors(&self, cube: Cube<D>) -> usize {
+        Self::offsets()
+            .iter()
+            .filter(|offset| {
+                let mut neighbor = cube;
+                for axis in 0..D {
+                    neighbor[axis] += offset[axis];
                 }
-            }
-        }
-        neighbors
+                self.cubes.contains(&neighbor)
+            })
+            .count()
     }
 }
 
 fn main() -> anyhow::Result<()> {
-    let grid = Grid::parse(INPUT)?;
-
-    let result = Grid::cycle_3d(&grid, 6)?;
+    let result = Grid::<3>::parse(INPUT)?.cycle(6);
     dbg!(result.num_active());
 
-    let result = Grid::cycle_4d(&grid, 6)?;
+    let result = Grid::<4>::parse(INPUT)?.cycle(6);
     dbg!(result.num_active());
 
+    let mut grid = Grid::<4>::parse(INPUT)?.into_symmetric()?;
+    for generation in 0..=6 {
+        dbg!(grid.stats(generation));
+        grid = grid.cycle(1);
+    }
+
+    // `render <cycles>` prints the layers of the 3D grid, `obj <cycles>` exports them
+    let args = std::env::args().collect::<Vec<_>>();
+    if let (Some(command), Some(cycles)) = (args.get(1), args.get(2).and_then(|arg| arg.parse::<u32>().ok())) {
+        let grid = Grid::<3>::parse(INPUT)?.into_symmetric()?.cycle(cycles);
+        match command.as_str() {
+            "render" => print!("{}", grid),
+            "obj" => print!("{}", grid.to_obj(&[])),
+            _ => return Err(anyhow::anyhow!("Unknown command {}", command)),
+        }
+    }
+
+    // higher dimensions in symmetric mode, e.g. `cargo run --release -- 6`
+    if let Some(dimensions) = std::env::args().nth(1).and_then(|arg| arg.parse::<usize>().ok()) {
+        let result = match dimensions {
+            5 => Grid::<5>::parse(INPUT)?.into_symmetric()?.cycle(6).num_active(),
+            6 => Grid::<6>::parse(INPUT)?.into_symmetric()?.cycle(6).num_active(),
+            _ => return Err(anyhow::anyhow!("Unsupported number of dimensions {}", dimensions)),
+        };
+        dbg!(result);
+    }
+
     Ok(())
 }
 
@@ -190,12 +241,13 @@ mod tests {
             ###
         "#;
 
-        let grid = Grid::parse(input);
+        let grid = Grid::<4>::parse(input);
         assert!(grid.is_ok());
 
         let grid = grid.unwrap();
         assert_eq!(5, grid.num_active());
-        assert_eq!(5, grid.neighbors(1, 1, 0, 0));
+        assert_eq!(5, grid.neighbors([1, 1, 0, 0]));
+        assert!(Grid::<1>::parse(input).is_err());
     }
 
     #[test]
@@ -206,10 +258,8 @@ mod tests {
             ###
         "#;
 
-        let grid = Grid::parse(input).unwrap();
-        let grid = Grid::cycle_3d(&grid, 1);
-        assert!(grid.is_ok());
-        let grid = grid.unwrap();
+        let grid = Grid::<3>::parse(input).unwrap();
+        let grid = grid.cycle(1);
 
         assert_eq!(11, grid.num_active());
     }
@@ -222,10 +272,10 @@ mod tests {
             ###
         "#;
 
-        let grid = Grid::parse(input).unwrap();
-        assert_eq!(21, Grid::cycle_3d(&grid, 2).unwrap().num_active());
-        assert_eq!(38, Grid::cycle_3d(&grid, 3).unwrap().num_active());
-        assert_eq!(112, Grid::cycle_3d(&grid, 6).unwrap().num_active());
+        let grid = Grid::<3>::parse(input).unwrap();
+        assert_eq!(21, grid.cycle(2).num_active());
+        assert_eq!(38, grid.cycle(3).num_active());
+        assert_eq!(112, grid.cycle(6).num_active());
     }
 
     #[test]
@@ -236,8 +286,55 @@ mod tests {
             ###
         "#;
 
-        let grid = Grid::parse(input).unwrap();
-        assert_eq!(29, Grid::cycle_4d(&grid, 1).unwrap().num_active());
-        assert_eq!(848, Grid::cycle_4d(&grid, 6).unwrap().num_active());
+        let grid = Grid::<4>::parse(input).unwrap();
+        assert_eq!(29, grid.cycle(1).num_active());
+        assert_eq!(848, grid.cycle(6).num_active());
+    }
+
+    #[test]
+    fn test_cycles_in_higher_dimensions() {
+        let input = r#"
+            .#.
+            ..#
+            ###
+        "#;
+
+        assert_eq!(5760, Grid::<5>::parse(input).unwrap().cycle(6).num_active());
+        assert_eq!(5, Grid::<2>::parse(input).unwrap().cycle(4).num_active());
+    }
+
+    #[test]
+    fn test_symmetric_mode_matches_full_simulation() {
+        let input = r#"
+            .#.
+            ..#
+            ###
+        "#;
+
+        let full = Grid::<3>::parse(input).unwrap();
+        let half = full.clone().into_symmetric().unwrap();
+        for cycles in 0..=4 {
+            let expected = full.cycle(cycles);
+            let result = half.cycle(cycles);
+            assert_eq!(expected.num_active(), result.num_active());
+            assert_eq!(expected, result.unfold());
+        }
+
+        let full = Grid::<4>::parse(input).unwrap();
+        let half = full.clone().into_symmetric().unwrap();
+        assert_eq!(full.cycle(6), half.cycle(6).unfold());
+        assert_eq!(848, half.cycle(6).num_active());
+
+        let half = Grid::<5>::parse(input).unwrap().into_symmetric().unwrap();
+        assert_eq!(5760, half.cycle(6).num_active());
+    }
+
+    #[test]
+    fn test_symmetric_mode_needs_flat_grid() {
+        let grid = Grid::<3>::parse("#").unwrap().cycle(1);
+        assert!(grid.into_symmetric().is_ok());
+
+        let grid = Grid::<3>::parse(".#.\n..#\n###").unwrap().cycle(1);
+        assert!(grid.into_symmetric().is_err());
     }
 }
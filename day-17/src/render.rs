@@ -0,0 +1,185 @@
+use std::fmt::{self, Display, Write};
+
+use crate::{Cube, Grid};
+
+/// Returns the name of the axis, as used by the puzzle
+fn axis_name(axis: usize) -> String {
+    match axis {
+        0 => "x".into(),
+        1 => "y".into(),
+        2 => "z".into(),
+        3 => "w".into(),
+        axis => format!("d{}", axis),
+    }
+}
+
+/// The population of a single generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats<const D: usize> {
+    pub generation: u32,
+    pub active: usize,
+    /// The number of x-y layers with at least one active cube
+    pub layers: usize,
+    /// The size of the bounding box per axis
+    pub extent: [usize; D],
+}
+
+impl<const D: usize> Grid<D> {
+    /// Returns the smallest and largest coordinates per axis of all active cubes
+    pub fn bounds(&self) -> Option<(Cube<D>, Cube<D>)> {
+        let mut cubes = self.cubes.iter();
+        let first = *cubes.next()?;
+
+        Some(cubes.fold((first, first), |(mut min, mut max), cube| {
+            for axis in 0..D {
+                min[axis] = min[axis].min(cube[axis]);
+                max[axis] = max[axis].max(cube[axis]);
+            }
+            (min, max)
+        }))
+    }
+
+    /// Returns the population statistics of the grid
+    pub fn stats(&self, generation: u32) -> Stats<D> {
+        let grid = self.unfold();
+
+        let mut layers = grid.cubes.iter().map(|cube| cube[2..].to_vec()).collect::<Vec<_>>();
+        layers.sort_unstable();
+        layers.dedup();
+
+        let mut extent = [0; D];
+        if let Some((min, max)) = grid.bounds() {
+            for axis in 0..D {
+                extent[axis] = (max[axis] - min[axis] + 1) as usize;
+            }
+        }
+
+        Stats { generation, active: grid.num_active(), layers: layers.len(), extent }
+    }
+
+    /// Exports the active cubes of the 3D slice as unit cubes in Wavefront OBJ format.
+    ///
+    /// `rest` selects the slice by the coordinates of all dimensions after `z`.
+    pub fn to_obj(&self, rest: &[i32]) -> String {
+        const CORNERS: [[i32; 3]; 8] = [
+            [0, 0, 0], [1, 0, 0], [1, 1, 0], [0, 1, 0],
+            [0, 0, 1], [1, 0, 1], [1, 1, 1], [0, 1, 1],
+        ];
+        const FACES: [[usize; 4]; 6] = [
+            [1, 4, 3, 2], [5, 6, 7, 8], [1, 2, 6, 5],
+            [2, 3, 7, 6], [3, 4, 8, 7], [4, 1, 5, 8],
+        ];
+
+        let grid = self.unfold();
+        let mut cubes = grid
+            .cubes
+            .iter()
+            .filter(|cube| D >= 3 && cube[3..] == *rest)
+            .map(|cube| [cube[0], cube[1], cube[2]])
+            .collect::<Vec<_>>();
+        cubes.sort_unstable();
+
+        let mut obj = String::new();
+        for (index, cube) in cubes.iter().enumerate() {
+            for corner in CORNERS.iter() {
+                writeln!(obj, "v {} {} {}", cube[0] + corner[0], cube[1] + corner[1], cube[2] + corner[2]).unwrap();
+            }
+            for face in FACES.iter() {
+                let [a, b, c, d] = face.map(|vertex| vertex + index * 8);
+                writeln!(obj, "f {} {} {} {}", a, b, c, d).unwrap();
+            }
+        }
+        obj
+    }
+}
+
+/// Prints every x-y layer within the bounds, labeled by its extra coordinates like "z=-1, w=0"
+impl<const D: usize> Display for Grid<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let grid = self.unfold();
+        let (min, max) = match grid.bounds() {
+            Some(bounds) => bounds,
+            None => return Ok(()),
+        };
+
+        // all layers, the last extra dimension changes slowest
+        let mut layers = vec![Vec::new()];
+        for axis in (2..D).rev() {
+            layers = layers
+                .into_iter()
+                .flat_map(|layer: Vec<i32>| {
+                    (min[axis]..=max[axis]).map(move |c| {
+                        let mut layer = layer.clone();
+                        layer.insert(0, c);
+                        layer
+                    })
+                })
+                .collect();
+        }
+
+        for (index, layer) in layers.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+
+            let label = layer
+                .iter()
+                .enumerate()
+                .map(|(offset, c)| format!("{}={}", axis_name(offset + 2), c))
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", label.join(", "))?;
+
+            let mut cube = [0; D];
+            cube[2..].copy_from_slice(layer);
+            for y in min[1]..=max[1] {
+                for x in min[0]..=max[0] {
+                    cube[0] = x;
+                    cube[1] = y;
+                    f.write_char(if grid.cubes.contains(&cube) { '#' } else { '.' })?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Grid;
+
+    const INPUT: &str = ".#.\n..#\n###";
+
+    #[test]
+    fn test_render_layers() {
+        let grid = Grid::<3>::parse(INPUT).unwrap().into_symmetric().unwrap().cycle(1);
+        let expected = "z=-1\n#..\n..#\n.#.\n\nz=0\n#.#\n.##\n.#.\n\nz=1\n#..\n..#\n.#.\n";
+        assert_eq!(expected, grid.to_string());
+
+        let grid = Grid::<4>::parse(INPUT).unwrap();
+        assert_eq!("z=0, w=0\n.#.\n..#\n###\n", grid.to_string());
+    }
+
+    #[test]
+    fn test_stats() {
+        let grid = Grid::<4>::parse(INPUT).unwrap().into_symmetric().unwrap();
+        let stats = grid.cycle(1).stats(1);
+
+        assert_eq!(29, stats.active);
+        assert_eq!(9, stats.layers);
+        assert_eq!([3, 3, 3, 3], stats.extent);
+        assert_eq!([3, 3, 1, 1], grid.stats(0).extent);
+    }
+
+    #[test]
+    fn test_export_obj() {
+        let grid = Grid::<4>::parse("#").unwrap();
+        let obj = grid.to_obj(&[0]);
+
+        assert_eq!(8, obj.lines().filter(|line| line.starts_with("v ")).count());
+        assert_eq!(6, obj.lines().filter(|line| line.starts_with("f ")).count());
+        assert!(obj.starts_with("v 0 0 0\nv 1 0 0\n"));
+        assert!(grid.to_obj(&[1]).is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::{self, Display};
+
+/// A binary operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Operator {
+    pub fn symbol(&self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Sub => '-',
+            Operator::Mul => '*',
+            Operator::Div => '/',
+            Operator::Rem => '%',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        match symbol {
+            '+' => Some(Operator::Add),
+            '-' => Some(Operator::Sub),
+            '*' => Some(Operator::Mul),
+            '/' => Some(Operator::Div),
+            '%' => Some(Operator::Rem),
+            _ => None,
+        }
+    }
+}
+
+/// The reason an expression cannot be evaluated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    /// The result of the sub expression does not fit into 64 bits
+    Overflow(String),
+    /// The sub expression divides by zero
+    DivisionByZero(String),
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Overflow(expression) => write!(f, "Overflow in {}", expression),
+            EvalError::DivisionByZero(expression) => write!(f, "Division by zero in {}", expression),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+/// The syntax tree of an arithmetic expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(i64),
+    Neg(Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Evaluates the expression, every operation is checked for overflow
+    pub fn eval(&self) -> Result<i64, EvalError> {
+        let overflow = || EvalError::Overflow(self.to_string());
+
+        match self {
+            Expr::Number(value) => Ok(*value),
+            Expr::Neg(expr) => expr.eval()?.checked_neg().ok_or_else(overflow),
+            Expr::Binary(operator, left, right) => {
+                let (left, right) = (left.eval()?, right.eval()?);
+                if right == 0 && matches!(operator, Operator::Div | Operator::Rem) {
+                    return Err(EvalError::DivisionByZero(self.to_string()));
+                }
+
+                match operator {
+                    Operator::Add => left.checked_add(right),
+                    Operator::Sub => left.checked_sub(right),
+                    Operator::Mul => left.checked_mul(right),
+                    Operator::Div => left.checked_div(right),
+                    Operator::Rem => left.checked_rem(right),
+                }
+                .ok_or_else(overflow)
+            }
+        }
+    }
+}
+
+/// Prints the expression with explicit parentheses around every operation
+impl Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(value) => write!(f, "{}", value),
+            Expr::Neg(expr) => write!(f, "(-{})", expr),
+            Expr::Binary(operator, left, right) => write!(f, "({} {} {})", left, operator.symbol(), right),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EvalError, Expr, Operator};
+
+    fn binary(operator: Operator, left: Expr, right: Expr) -> Expr {
+        Expr::Binary(operator, Box::new(left), Box::new(right))
+    }
+
+    #[test]
+    fn test_eval_and_print() {
+        let expr = binary(Operator::Mul, Expr::Neg(Box::new(Expr::Number(3))), binary(Operator::Rem, Expr::Number(7), Expr::Number(4)));
+        assert_eq!("((-3) * (7 % 4))", expr.to_string());
+        assert_eq!(Ok(-9), expr.eval());
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let expr = binary(Operator::Add, Expr::Number(i64::MAX), Expr::Number(1));
+        assert_eq!(Err(EvalError::Overflow(format!("({} + 1)", i64::MAX))), expr.eval());
+
+        let expr = binary(Operator::Div, Expr::Number(1), binary(Operator::Sub, Expr::Number(2), Expr::Number(2)));
+        assert_eq!(Err(EvalError::DivisionByZero("(1 / (2 - 2))".into())), expr.eval());
+
+        let expr = binary(Operator::Div, Expr::Number(i64::MIN), Expr::Neg(Box::new(Expr::Number(1))));
+        assert!(matches!(expr.eval(), Err(EvalError::Overflow(_))));
+    }
+}
@@ -1,40 +1,7 @@
+mod expression;
+mod parser;
 
-peg::parser!{
-    /// Equation parser, parses the input and calculates the value of the equation
-    ///
-    /// # Example
-    /// ```
-    /// 3 + 6 + (3 * 9)
-    /// ```
-    ///
-    grammar parser() for str {
-        rule number() -> u64
-            = n:$(['0'..='9']+) { n.parse().unwrap() }
-
-        rule separator() = [' ']?
-
-        /// Left associated parser, multiplication & addition have same precedence
-        /// For more details see: https://docs.rs/peg/0.6.3/peg/#precedence-climbing
-        pub(crate) rule part1() -> u64
-            = precedence! {
-                x:(@) separator() "+" separator() y:@ { x + y }
-                x:(@) separator() "*" separator() y:@ { x * y }
-                "(" e:part1() ")" { e }
-                n:number() { n }
-            }
-
-        /// Addition has precedence over multiplication
-        pub(crate) rule part2() -> u64
-            = precedence! {
-                x:(@) separator() "*" separator() y:@ { x * y }
-                --
-                x:(@) separator() "+" separator() y:@ { x + y }
-                --
-                "(" e:part2() ")" { e }
-                n:number() { n }
-            }
-    }
-}
+use parser::{parse_expression, Associativity, PrecedenceTable};
 
 /// Parses the string content as a list of equations
 fn parse(content: &str) -> anyhow::Result<Vec<String>> {
@@ -47,23 +14,48 @@ fn parse(content: &str) -> anyhow::Result<Vec<String>> {
     Ok(lines)
 }
 
-fn main() -> anyhow::Result<()> {
-    let result = parse(include_str!("equations.txt"))?
+/// Solves every equation and sums up the results, fails on the first invalid equation
+/// or if the sum overflows
+fn sum_equations<F>(equations: &[String], solve: F) -> anyhow::Result<i64>
+where
+    F: Fn(&str) -> anyhow::Result<i64>,
+{
+    let results = equations
         .iter()
-        .map(|line| parser::part1(line))
-        .filter_map(Result::ok)
-        .sum::<u64>();
-
-    dbg!(result);
+        .map(|line| solve(line).map_err(|err| anyhow::anyhow!("{} in '{}'", err, line)))
+        .collect::<anyhow::Result<Vec<i64>>>()?;
 
-    let result = parse(include_str!("equations.txt"))?
+    results
         .iter()
-        .map(|line| parser::part2(line))
-        .filter_map(Result::ok)
-        .sum::<u64>();
+        .try_fold(0i64, |sum, &value| sum.checked_add(value))
+        .ok_or_else(|| anyhow::anyhow!("Sum of all equations overflows"))
+}
+
+fn main() -> anyhow::Result<()> {
+    let equations = parse(include_str!("equations.txt"))?;
+
+    let result = sum_equations(&equations, parser::part1)?;
+    dbg!(result);
 
+    let result = sum_equations(&equations, parser::part2)?;
     dbg!(result);
 
+    // `cargo run -- [--right] "<expression>"` prints the expression with parentheses and its value,
+    // using the usual arithmetic rules, `--right` evaluates operators of the same precedence right to left
+    let mut args = std::env::args().skip(1).peekable();
+    let associativity = match args.peek().map(String::as_str) {
+        Some("--right") => {
+            args.next();
+            Associativity::Right
+        }
+        _ => Associativity::Left,
+    };
+    if let Some(input) = args.next() {
+        let table = PrecedenceTable::standard().with_associativity(associativity);
+        let expr = parse_expression(&input, &table)?;
+        println!("{} = {}", expr, expr.eval()?);
+    }
+
     Ok(())
 }
 
@@ -97,4 +89,19 @@ mod tests {
         assert_eq!(669060, parser::part2("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))").unwrap());
         assert_eq!(23340, parser::part2("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2").unwrap());
     }
+
+    #[test]
+    fn test_sum_equations_reports_errors() {
+        let equations = parse("1 + 2\n3 * 4").unwrap();
+        assert_eq!(15, sum_equations(&equations, parser::part1).unwrap());
+
+        let equations = parse("1 + 2\n9999999999 * 9999999999").unwrap();
+        assert!(sum_equations(&equations, parser::part1).is_err());
+
+        let equations = parse("1 + 2\n3 - 4").unwrap();
+        assert!(sum_equations(&equations, parser::part1).is_err());
+
+        let equations = parse("9223372036854775807\n1").unwrap();
+        assert!(sum_equations(&equations, parser::part1).is_err());
+    }
 }
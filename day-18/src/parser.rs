@@ -0,0 +1,265 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    iter::Peekable,
+    str::CharIndices,
+};
+
+use crate::expression::{Expr, Operator};
+
+/// The direction in which operators of the same precedence group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+/// The precedence and associativity of every operator, a higher precedence binds tighter.
+///
+/// Operators that are not in the table are rejected by the parser.
+#[derive(Debug, Clone, Default)]
+pub struct PrecedenceTable {
+    binary: HashMap<Operator, (u8, Associativity)>,
+    unary_minus: Option<u8>,
+}
+
+impl PrecedenceTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the binary operator
+    pub fn binary(mut self, operator: Operator, precedence: u8, associativity: Associativity) -> Self {
+        self.binary.insert(operator, (precedence, associativity));
+        self
+    }
+
+    /// Adds the unary minus, it binds tighter than binary operators of the same precedence
+    pub fn unary_minus(mut self, precedence: u8) -> Self {
+        self.unary_minus = Some(precedence);
+        self
+    }
+
+    /// Changes the associativity of all binary operators
+    pub fn with_associativity(mut self, associativity: Associativity) -> Self {
+        for entry in self.binary.values_mut() {
+            entry.1 = associativity;
+        }
+        self
+    }
+
+    /// All operators have the same precedence and are evaluated from left to right
+    pub fn equal() -> Self {
+        Self::new()
+            .binary(Operator::Add, 1, Associativity::Left)
+            .binary(Operator::Mul, 1, Associativity::Left)
+    }
+
+    /// Addition binds tighter than multiplication
+    pub fn addition_first() -> Self {
+        Self::new()
+            .binary(Operator::Add, 2, Associativity::Left)
+            .binary(Operator::Mul, 1, Associativity::Left)
+    }
+
+    /// The usual arithmetic rules, multiplication, division and remainder bind tighter
+    /// than addition and subtraction, unary minus binds tightest
+    pub fn standard() -> Self {
+        Self::new()
+            .binary(Operator::Add, 1, Associativity::Left)
+            .binary(Operator::Sub, 1, Associativity::Left)
+            .binary(Operator::Mul, 2, Associativity::Left)
+            .binary(Operator::Div, 2, Associativity::Left)
+            .binary(Operator::Rem, 2, Associativity::Left)
+            .unary_minus(3)
+    }
+}
+
+/// The reason an expression cannot be parsed, positions are byte offsets into the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    UnexpectedChar(usize, char),
+    UnexpectedEnd,
+    /// The operator is not part of the precedence table
+    UnknownOperator(usize, char),
+    /// The number does not fit into 64 bits
+    InvalidNumber(usize, String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedChar(position, c) => write!(f, "Unexpected '{}' at {}", c, position),
+            ParseError::UnexpectedEnd => write!(f, "Unexpected end of expression"),
+            ParseError::UnknownOperator(position, c) => write!(f, "Unknown operator '{}' at {}", c, position),
+            ParseError::InvalidNumber(position, number) => write!(f, "Invalid number {} at {}", number, position),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A Pratt parser, the binding power of the operators is derived from the precedence table
+struct Parser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    table: &'a PrecedenceTable,
+}
+
+impl<'a> Parser<'a> {
+    /// Returns the next character that is not whitespace without consuming it
+    fn peek(&mut self) -> Option<(usize, char)> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+        self.chars.peek().copied()
+    }
+
+    fn number(&mut self, start: usize) -> Result<Expr, ParseError> {
+        let mut end = start;
+        while let Some(&(position, c)) = self.chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            end = position + c.len_utf8();
+            self.chars.next();
+        }
+
+        let number = &self.input[start..end];
+        number
+            .parse::<i64>()
+            .map(Expr::Number)
+            .map_err(|_| ParseError::InvalidNumber(start, number.into()))
+    }
+
+    /// Parses a number, a parenthesized expression or a negation
+    fn prefix(&mut self) -> Result<Expr, ParseError> {
+        match self.peek() {
+            Some((position, c)) if c.is_ascii_digit() => self.number(position),
+            Some((_, '(')) => {
+                self.chars.next();
+                let expr = self.expression(0)?;
+                match self.peek() {
+                    Some((_, ')')) => {
+                        self.chars.next();
+                        Ok(expr)
+                    }
+                    Some((position, c)) => Err(ParseError::UnexpectedChar(position, c)),
+                    None => Err(ParseError::UnexpectedEnd),
+                }
+            }
+            Some((position, '-')) => {
+                let precedence = self.table.unary_minus.ok_or(ParseError::UnknownOperator(position, '-'))?;
+                self.chars.next();
+                Ok(Expr::Neg(Box::new(self.expression(precedence as u32 * 2 + 1)?)))
+            }
+            Some((position, c)) => Err(ParseError::UnexpectedChar(position, c)),
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+
+    /// Parses operators as long as they bind at least with `min_power` to the left
+    fn expression(&mut self, min_power: u32) -> Result<Expr, ParseError> {
+        let mut left = self.prefix()?;
+
+        while let Some((position, c)) = self.peek() {
+            let operator = match Operator::from_symbol(c) {
+                Some(operator) => operator,
+                None if c == ')' => break,
+                None => return Err(ParseError::UnexpectedChar(position, c)),
+            };
+            let (precedence, associativity) = *self
+                .table
+                .binary
+                .get(&operator)
+                .ok_or(ParseError::UnknownOperator(position, c))?;
+
+            let left_power = precedence as u32 * 2;
+            if left_power < min_power {
+                break;
+            }
+            let right_power = match associativity {
+                Associativity::Left => left_power + 1,
+                Associativity::Right => left_power,
+            };
+
+            self.chars.next();
+            let right = self.expression(right_power)?;
+            left = Expr::Binary(operator, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+}
+
+/// Parses the expression with the given operator precedences
+pub fn parse_expression(input: &str, table: &PrecedenceTable) -> Result<Expr, ParseError> {
+    let mut parser = Parser { input, chars: input.char_indices().peekable(), table };
+    let expr = parser.expression(0)?;
+
+    match parser.peek() {
+        Some((position, c)) => Err(ParseError::UnexpectedChar(position, c)),
+        None => Ok(expr),
+    }
+}
+
+/// Evaluates the expression, multiplication & addition have same precedence
+pub fn part1(input: &str) -> anyhow::Result<i64> {
+    Ok(parse_expression(input, &PrecedenceTable::equal())?.eval()?)
+}
+
+/// Evaluates the expression, addition has precedence over multiplication
+pub fn part2(input: &str) -> anyhow::Result<i64> {
+    Ok(parse_expression(input, &PrecedenceTable::addition_first())?.eval()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_expression, Associativity, ParseError, PrecedenceTable};
+    use crate::expression::Operator;
+
+    fn print(input: &str, table: &PrecedenceTable) -> String {
+        parse_expression(input, table).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_precedence_table() {
+        let standard = PrecedenceTable::standard();
+        assert_eq!("((1 + (2 * 3)) - (4 % 3))", print("1 + 2 * 3 - 4 % 3", &standard));
+        assert_eq!("(((-2) * 3) / (-(-1)))", print("-2 * 3 / --1", &standard));
+        assert_eq!("((1 + 2) * 3)", print("1 + 2 * 3", &PrecedenceTable::equal()));
+        assert_eq!("(1 * (2 + 3))", print("1 * 2 + 3", &PrecedenceTable::addition_first()));
+
+        let low_minus = PrecedenceTable::standard().unary_minus(1);
+        assert_eq!("((-(2 * 3)) + 1)", print("-2 * 3 + 1", &low_minus));
+    }
+
+    #[test]
+    fn test_associativity() {
+        let table = PrecedenceTable::new()
+            .binary(Operator::Sub, 1, Associativity::Right)
+            .binary(Operator::Div, 2, Associativity::Left);
+
+        assert_eq!("(8 - (4 - 2))", print("8 - 4 - 2", &table));
+        assert_eq!("((8 / 4) / 2)", print("8 / 4 / 2", &table));
+        assert_eq!(6, parse_expression("8 - 4 - 2", &table).unwrap().eval().unwrap());
+
+        let right = PrecedenceTable::standard().with_associativity(Associativity::Right);
+        assert_eq!("((8 / (4 / 2)) - (3 - 1))", print("8 / 4 / 2 - 3 - 1", &right));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let table = PrecedenceTable::equal();
+        assert_eq!(Err(ParseError::UnknownOperator(2, '-')), parse_expression("1 - 2", &table));
+        assert_eq!(Err(ParseError::UnknownOperator(0, '-')), parse_expression("-2", &table));
+        assert_eq!(Err(ParseError::UnexpectedEnd), parse_expression("(1 + 2", &table));
+        assert_eq!(Err(ParseError::UnexpectedChar(5, ')')), parse_expression("1 + 2)", &table));
+        assert_eq!(Err(ParseError::UnexpectedChar(2, 'x')), parse_expression("1 x 2", &table));
+        assert_eq!(Err(ParseError::UnexpectedEnd), parse_expression("1 +", &table));
+        assert!(matches!(parse_expression("99999999999999999999", &table), Err(ParseError::InvalidNumber(0, _))));
+    }
+}
@@ -1,5 +1,7 @@
 use std::collections::{HashMap, HashSet};
 
+use automaton::SparseAutomaton;
+
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Dir {
@@ -92,7 +94,6 @@ impl Tile {
 
 #[derive(Debug)]
 struct Floor {
-    pub tiles: Vec<Tile>,
     pub last_tiles: Vec<Pos>,
 }
 
@@ -101,7 +102,6 @@ impl Floor {
         let last_tiles = Self::last_tiles(&tiles);
 
         Self {
-            tiles,
             last_tiles,
         }
     }
@@ -132,48 +132,23 @@ impl Floor {
 
     /// Returns the number of black tiles
     pub fn num_black_tiles(&self) -> u64 {
-        self.black_tiles().iter().count() as u64
+        self.black_tiles().len() as u64
     }
 
-    /// Apply "game of life" rules to the floor grid of existing tiles
-    /// This creates a new Grid with the new flipped tiles
+    /// Apply "game of life" rules to the floor grid of existing tiles, a black tile stays black
+    /// with 1 or 2 black neighbors, a white tile becomes black with exactly 2 black neighbors
     pub fn flip_tiles(&self, num_days: u64) -> u64 {
-        let mut black_tiles = self.black_tiles();
-
-        for round in 0..num_days {
-            // get all relevant adjacent white tiles
-            let mut white_tiles = HashSet::new();
-            for black_tile in &black_tiles {
-                let neighbors = black_tile.adjacent();
-                for neighbor in &neighbors {
-                    if !black_tiles.contains(neighbor) {
-                        white_tiles.insert(neighbor.clone());
-                    }
-                }
-            }
-
-            let mut new_black_tiles: HashSet<Pos> = HashSet::new();
-
-            // apply rules to all black tiles
-            for black_tile in &black_tiles {
-                let adjacent = black_tile.adjacent().intersection(&black_tiles).count();
-                if adjacent == 1 || adjacent == 2 {
-                    new_black_tiles.insert(black_tile.clone());
-                }
+        let adjacent = |tile: &Pos, visit: &mut dyn FnMut(Pos)| {
+            for neighbor in tile.adjacent() {
+                visit(neighbor);
             }
+        };
+        let flip = |black: bool, count: usize| count == 2 || (black && count == 1);
 
-            // apply rule to all white neighbor tiles
-            for white_tile in &white_tiles {
-                let adjacent = white_tile.adjacent().intersection(&black_tiles).count();
-                if adjacent == 2 {
-                    new_black_tiles.insert(white_tile.clone());
-                }
-            }
-
-            black_tiles = new_black_tiles;
-        }
+        let mut floor = SparseAutomaton::new(self.black_tiles(), adjacent, flip);
+        floor.run(num_days as usize);
 
-        black_tiles.len() as u64
+        floor.cells().len() as u64
     }
 }
 
@@ -183,7 +158,7 @@ fn parse_tiles(content: &str) -> anyhow::Result<Vec<Tile>> {
         .lines()
         .map(str::trim)
         .filter(|&line| !line.is_empty())
-        .map(|line| Tile::parse(line))
+        .map(Tile::parse)
         .filter_map(Result::ok)
         .collect();
 